@@ -15,6 +15,7 @@ fn main() -> Result<()> {
             Ok(_) => {}
             Err(err) => {
                 log::error!("{}", err);
+                std::process::exit(1);
             }
         }
     } else if version {
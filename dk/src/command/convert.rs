@@ -0,0 +1,277 @@
+use crate::command::Command;
+use crate::command::formatter::{FormattedValue, FormattedValueType};
+use crate::command::http::HttpClientArgs;
+use crate::command::json::Json;
+use itertools::Itertools;
+use serde_json::{Value, json};
+use std::fs;
+use std::path::PathBuf;
+
+#[derive(clap::Args)]
+pub struct ConvertArgs {
+    #[arg(
+        help = "input, support string, file-path, url, cmd",
+        default_value = ""
+    )]
+    input: Json,
+    #[arg(
+        short,
+        long,
+        help = "target type, json/jsonl/yaml/yamlstream/toml/csv/xml"
+    )]
+    to: FormattedValueType,
+    #[arg(
+        long,
+        help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+        alias = "it"
+    )]
+    input_type: Option<FormattedValueType>,
+    #[arg(short, long, help = "file to write output")]
+    file: Option<PathBuf>,
+    #[command(flatten)]
+    client: HttpClientArgs,
+}
+
+impl Command for ConvertArgs {
+    fn run(&self) -> crate::Result<()> {
+        let Self {
+            input,
+            to,
+            input_type,
+            file,
+            client,
+        } = self;
+        let value = input.to_formatted_value(*input_type, &client.options()?)?;
+        let Conversion { value, lossy } = convert(value, *to)?;
+        for it in &lossy {
+            eprintln!("lossy: {it}");
+        }
+        let content = match value {
+            FormattedValue::Jsonl(values) => values
+                .iter()
+                .map(serde_json::to_string)
+                .collect::<Result<Vec<_>, _>>()?
+                .join("\n"),
+            value => value.to_string_pretty()?,
+        };
+        if let Some(file) = file {
+            fs::write(file, content)?;
+            println!("write to {}", file.display());
+        } else {
+            println!("{}", content.trim_end());
+        }
+        Ok(())
+    }
+}
+
+/// The converted value and the parts of the input the target type can not represent.
+pub struct Conversion {
+    pub value: FormattedValue,
+    pub lossy: Vec<String>,
+}
+
+pub fn convert(value: FormattedValue, to: FormattedValueType) -> crate::Result<Conversion> {
+    if value.type_() == to {
+        return Ok(Conversion {
+            value,
+            lossy: vec![],
+        });
+    }
+    let mut lossy = Lossy::default();
+    let value = match to {
+        FormattedValueType::Toml => {
+            let mut json = Value::try_from(value)?;
+            drop_nulls(&mut json, "$", &mut lossy);
+            if !json.is_object() {
+                lossy.add(
+                    "$",
+                    "wrapped in table `root`, TOML top level must be a table",
+                );
+                json = json!({ "root": json });
+            }
+            FormattedValue::Json(json).convert(to)?
+        }
+        FormattedValueType::Csv | FormattedValueType::Tsv => {
+            let json = Value::try_from(value)?;
+            check_csv(&json, &mut lossy);
+            FormattedValue::Json(json).convert(to)?
+        }
+        FormattedValueType::Xml => {
+            let json = Value::try_from(value)?;
+            check_xml(&json, "$", &mut lossy);
+            FormattedValue::Json(json).convert(to)?
+        }
+        _ => value.convert(to)?,
+    };
+    Ok(Conversion {
+        value,
+        lossy: lossy.into_messages(),
+    })
+}
+
+/// Lossy spots grouped by path pattern (array indexes as `[*]`) and reason.
+#[derive(Default)]
+struct Lossy(Vec<(String, &'static str, usize)>);
+
+impl Lossy {
+    fn add(&mut self, path: &str, reason: &'static str) {
+        match self
+            .0
+            .iter_mut()
+            .find(|(p, r, _)| p == path && *r == reason)
+        {
+            Some((_, _, count)) => *count += 1,
+            None => self.0.push((path.to_string(), reason, 1)),
+        }
+    }
+
+    fn into_messages(self) -> Vec<String> {
+        self.0
+            .into_iter()
+            .map(|(path, reason, count)| match count {
+                1 => format!("{path}: {reason}"),
+                count => format!("{path}: {reason} ({count} times)"),
+            })
+            .collect_vec()
+    }
+}
+
+fn drop_nulls(value: &mut Value, path: &str, lossy: &mut Lossy) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|key, value| {
+                if value.is_null() {
+                    lossy.add(
+                        &Json::jsonpath_child(path, key),
+                        "null dropped, TOML has no null",
+                    );
+                }
+                !value.is_null()
+            });
+            for (key, value) in map.iter_mut() {
+                drop_nulls(value, &Json::jsonpath_child(path, key), lossy);
+            }
+        }
+        Value::Array(values) => {
+            let path = format!("{path}[*]");
+            values.retain(|value| {
+                if value.is_null() {
+                    lossy.add(&path, "null dropped, TOML has no null");
+                }
+                !value.is_null()
+            });
+            for value in values.iter_mut() {
+                drop_nulls(value, &path, lossy);
+            }
+        }
+        _ => {}
+    }
+}
+
+fn check_csv(value: &Value, lossy: &mut Lossy) {
+    let values = match value {
+        Value::Array(values) => values.as_slice(),
+        value => std::slice::from_ref(value),
+    };
+    let headers = values
+        .iter()
+        .filter_map(Value::as_object)
+        .flat_map(|it| it.keys())
+        .unique()
+        .collect_vec();
+    for value in values {
+        match value {
+            Value::Object(map) => {
+                for header in &headers {
+                    let path = Json::jsonpath_child("$[*]", header);
+                    match map.get(header.as_str()) {
+                        None => lossy.add(&path, "missing, written as an empty field"),
+                        Some(Value::Object(_) | Value::Array(_)) => {
+                            lossy.add(&path, "nested value written as JSON text")
+                        }
+                        _ => {}
+                    }
+                }
+            }
+            Value::Array(fields) if fields.iter().any(|it| it.is_object() || it.is_array()) => {
+                lossy.add("$[*][*]", "nested value written as JSON text");
+            }
+            _ => {}
+        }
+    }
+}
+
+fn check_xml(value: &Value, path: &str, lossy: &mut Lossy) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                check_xml(value, &Json::jsonpath_child(path, key), lossy);
+            }
+        }
+        Value::Array(values) => {
+            let path = format!("{path}[*]");
+            for value in values {
+                check_xml(value, &path, lossy);
+            }
+        }
+        Value::Null | Value::Bool(_) | Value::Number(_) => {
+            lossy.add(path, "written as text, XML has no typed values")
+        }
+        Value::String(_) => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::convert;
+    use crate::command::formatter::{FormattedValue, FormattedValueType};
+    use serde_json::json;
+
+    #[test]
+    fn drops_nulls_and_wraps_top_level_for_toml() {
+        let input =
+            FormattedValue::Json(json!([{"name": "a", "tag": null, "7": null}, {"name": "b"}]));
+        let conversion = convert(input, FormattedValueType::Toml).unwrap();
+        assert_eq!(
+            conversion.lossy,
+            vec![
+                "$[*]['7']: null dropped, TOML has no null",
+                "$[*].tag: null dropped, TOML has no null",
+                "$: wrapped in table `root`, TOML top level must be a table"
+            ]
+        );
+        assert_eq!(
+            conversion.value.to_string().unwrap(),
+            "[[root]]\nname = \"a\"\n\n[[root]]\nname = \"b\"\n"
+        );
+    }
+
+    #[test]
+    fn reports_nested_and_missing_csv_fields() {
+        let input = FormattedValue::Jsonl(vec![
+            json!({"id": 1, "tags": ["x"]}),
+            json!({"id": 2, "tags": ["y", "z"]}),
+            json!({"id": 3}),
+        ]);
+        let conversion = convert(input, FormattedValueType::Csv).unwrap();
+        assert_eq!(
+            conversion.lossy,
+            vec![
+                "$[*].tags: nested value written as JSON text (2 times)",
+                "$[*].tags: missing, written as an empty field"
+            ]
+        );
+        assert_eq!(
+            conversion.value.to_string().unwrap(),
+            "id,tags\n1,\"[\"\"x\"\"]\"\n2,\"[\"\"y\"\",\"\"z\"\"]\"\n3,\n"
+        );
+    }
+
+    #[test]
+    fn lossless_conversions_report_nothing() {
+        let input = FormattedValue::Json(json!({"a": [1, null, {"b": true}]}));
+        let conversion = convert(input, FormattedValueType::Yaml).unwrap();
+        assert!(conversion.lossy.is_empty());
+        assert!(matches!(conversion.value, FormattedValue::Yaml(_)));
+    }
+}
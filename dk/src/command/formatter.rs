@@ -1,16 +1,21 @@
-use crate::command::json::Json;
+use crate::command::http_parser::HttpClientOptions;
+use crate::command::json::{Json, xml};
 use anyhow::anyhow;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs;
+use std::path::Path;
 use std::str::FromStr;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FormattedValue {
     Json(Value),
     Jsonl(Vec<Value>),
-    //Yaml(serde_yaml::Value),
+    Yaml(serde_yaml::Value),
+    YamlStream(Vec<serde_yaml::Value>),
     Toml(toml::Value),
+    Csv(Table),
+    Xml(String),
     Text(String),
 }
 
@@ -20,8 +25,13 @@ pub enum FormattedValueType {
     #[default]
     Json,
     Jsonl,
-    //Yaml,
+    Yaml,
+    YamlStream,
     Toml,
+    Csv,
+    /// A table delimited by tabs
+    Tsv,
+    Xml,
     Text,
 }
 
@@ -40,8 +50,16 @@ impl TryFrom<FormattedValue> for Value {
         let value = match value {
             FormattedValue::Json(value) => value,
             FormattedValue::Jsonl(value) => Value::Array(value),
-            //FormattedValue::Yaml(value) => serde_json::to_value(value)?,
+            FormattedValue::Yaml(value) => serde_json::to_value(value)?,
+            FormattedValue::YamlStream(values) => Value::Array(
+                values
+                    .into_iter()
+                    .map(serde_json::to_value)
+                    .collect::<Result<Vec<_>, _>>()?,
+            ),
             FormattedValue::Toml(value) => serde_json::to_value(value)?,
+            FormattedValue::Csv(table) => Value::Array(table.to_records()),
+            FormattedValue::Xml(value) => xml::xml_to_value(&value)?,
             FormattedValue::Text(value) => serde_json::Value::String(value),
         };
         Ok(value)
@@ -54,6 +72,10 @@ impl FormattedValue {
             FormattedValueType::Json => match self {
                 FormattedValue::Json(_) => Ok(self),
                 FormattedValue::Jsonl(value) => Ok(FormattedValue::Json(Value::Array(value))),
+                value @ (FormattedValue::Yaml(_)
+                | FormattedValue::YamlStream(_)
+                | FormattedValue::Csv(_)
+                | FormattedValue::Xml(_)) => Ok(FormattedValue::Json(value.try_into()?)),
                 FormattedValue::Toml(value) => {
                     Ok(FormattedValue::Json(serde_json::to_value(value)?))
                 }
@@ -74,6 +96,35 @@ impl FormattedValue {
                     .convert(FormattedValueType::Json)?
                     .convert(FormattedValueType::Jsonl)?),
             },
+            FormattedValueType::Yaml => match self {
+                FormattedValue::Json(value) => {
+                    Ok(FormattedValue::Yaml(serde_yaml::to_value(value)?))
+                }
+                FormattedValue::Yaml(_) => Ok(self),
+                _ => Ok(self
+                    .convert(FormattedValueType::Json)?
+                    .convert(FormattedValueType::Yaml)?),
+            },
+            FormattedValueType::YamlStream => match self {
+                FormattedValue::Json(value) => {
+                    let values = if let Value::Array(values) = value {
+                        values
+                    } else {
+                        vec![value]
+                    };
+                    Ok(FormattedValue::YamlStream(
+                        values
+                            .into_iter()
+                            .map(serde_yaml::to_value)
+                            .collect::<Result<Vec<_>, _>>()?,
+                    ))
+                }
+                FormattedValue::Yaml(value) => Ok(FormattedValue::YamlStream(vec![value])),
+                FormattedValue::YamlStream(_) => Ok(self),
+                _ => Ok(self
+                    .convert(FormattedValueType::Json)?
+                    .convert(FormattedValueType::YamlStream)?),
+            },
             FormattedValueType::Toml => match self {
                 FormattedValue::Json(value) => {
                     Ok(FormattedValue::Toml(serde_json::from_value(value)?))
@@ -83,6 +134,27 @@ impl FormattedValue {
                     .convert(FormattedValueType::Json)?
                     .convert(FormattedValueType::Toml)?),
             },
+            FormattedValueType::Csv | FormattedValueType::Tsv => match self {
+                FormattedValue::Json(value) => Ok(FormattedValue::Csv(Table::from_value(
+                    &value,
+                    type_to.delimiter(),
+                )?)),
+                FormattedValue::Csv(table) if type_to == FormattedValueType::Csv => {
+                    Ok(FormattedValue::Csv(table))
+                }
+                FormattedValue::Csv(table) => Ok(FormattedValue::Csv(Table {
+                    delimiter: b'\t',
+                    ..table
+                })),
+                _ => Ok(self.convert(FormattedValueType::Json)?.convert(type_to)?),
+            },
+            FormattedValueType::Xml => match self {
+                FormattedValue::Json(value) => Ok(FormattedValue::Xml(xml::value_to_xml(&value)?)),
+                FormattedValue::Xml(_) => Ok(self),
+                _ => Ok(self
+                    .convert(FormattedValueType::Json)?
+                    .convert(FormattedValueType::Xml)?),
+            },
             FormattedValueType::Text => match self {
                 FormattedValue::Json(value) => {
                     Ok(FormattedValue::Text(serde_json::to_string_pretty(&value)?))
@@ -95,12 +167,29 @@ impl FormattedValue {
         }
     }
 
+    /// Records of a stream (JSONL, multi-document YAML, CSV rows), or the value itself.
+    pub fn into_records(self) -> crate::Result<Vec<Value>> {
+        match self {
+            FormattedValue::Jsonl(values) => Ok(values),
+            FormattedValue::YamlStream(values) => Ok(values
+                .into_iter()
+                .map(serde_json::to_value)
+                .collect::<Result<Vec<_>, _>>()?),
+            FormattedValue::Csv(table) => Ok(table.to_records()),
+            value => Ok(vec![value.try_into()?]),
+        }
+    }
+
     pub fn type_(&self) -> FormattedValueType {
         match self {
             FormattedValue::Json(_) => FormattedValueType::Json,
             FormattedValue::Jsonl(_) => FormattedValueType::Jsonl,
-            //FormattedValue::Yaml(_) => FormattedValueType::Yaml,
+            FormattedValue::Yaml(_) => FormattedValueType::Yaml,
+            FormattedValue::YamlStream(_) => FormattedValueType::YamlStream,
             FormattedValue::Toml(_) => FormattedValueType::Toml,
+            FormattedValue::Csv(table) if table.delimiter == b'\t' => FormattedValueType::Tsv,
+            FormattedValue::Csv(_) => FormattedValueType::Csv,
+            FormattedValue::Xml(_) => FormattedValueType::Xml,
             FormattedValue::Text(_) => FormattedValueType::Text,
         }
     }
@@ -108,7 +197,8 @@ impl FormattedValue {
         match self {
             FormattedValue::Json(value) => Ok(serde_json::to_string_pretty(value)?),
             FormattedValue::Jsonl(value) => Ok(serde_json::to_string_pretty(value)?),
-            //FormattedValue::Yaml(value) => Ok(serde_yaml::to_string(value)?),
+            FormattedValue::Yaml(value) => Ok(serde_yaml::to_string(value)?),
+            FormattedValue::YamlStream(values) => yaml_stream_to_string(values),
             FormattedValue::Toml(value) => {
                 if let Ok(string) = toml::to_string_pretty(value) {
                     Ok(string)
@@ -116,6 +206,8 @@ impl FormattedValue {
                     FormattedValue::Json(serde_json::to_value(value.clone())?).to_string_pretty()
                 }
             }
+            FormattedValue::Csv(table) => table.to_text(),
+            FormattedValue::Xml(value) => xml::reformat(value, true),
             FormattedValue::Text(value) => Ok(value.clone()),
         }
     }
@@ -124,8 +216,11 @@ impl FormattedValue {
         match self {
             FormattedValue::Json(value) => Ok(serde_json::to_string(value)?),
             FormattedValue::Jsonl(value) => Ok(serde_json::to_string(value)?),
-            //FormattedValue::Yaml(value) => Ok(serde_yaml::to_string(value)?),
+            FormattedValue::Yaml(value) => Ok(serde_yaml::to_string(value)?),
+            FormattedValue::YamlStream(values) => yaml_stream_to_string(values),
             FormattedValue::Toml(value) => Ok(toml::to_string(value)?),
+            FormattedValue::Csv(table) => table.to_text(),
+            FormattedValue::Xml(value) => xml::reformat(value, false),
             FormattedValue::Text(value) => Ok(value.clone()),
         }
     }
@@ -141,52 +236,434 @@ pub fn parse_formatted_value(input: &str) -> FormattedValue {
                 return parse_formatted_value(&input);
             }
             Json::Filepath(path) => {
-                if let Ok(input) = fs::read_to_string(path) {
-                    return parse_formatted_value(&input);
+                if let Ok(input) = fs::read_to_string(&path) {
+                    return parse_file_content(&path, &input);
                 }
             }
+            // a url in the content itself is fetched with the default client options
             Json::HttpRequest(http_request) => {
-                if let Ok(value) = (&http_request).try_into() {
+                if let Ok(value) = http_request.to_formatted_value(&HttpClientOptions::default()) {
                     return value;
                 }
             }
+            Json::Har(har) => return FormattedValue::Json(har.to_value()),
             Json::String(_) => {}
         }
     }
+    guess_formatted_value(input)
+}
+
+/// The content of a file, `.csv` and `.tsv` files are read as tables instead of
+/// guessing, the delimiter of `.csv` files is still sniffed as they may use `;`.
+pub(crate) fn parse_file_content(path: &Path, input: &str) -> FormattedValue {
+    let extension = path
+        .extension()
+        .map(|it| it.to_string_lossy().to_lowercase());
+    let table = match extension.as_deref() {
+        Some("csv") => Table::parse(input)
+            .map(FormattedValue::Csv)
+            .or_else(|_| parse_formatted_value_as(input, FormattedValueType::Csv)),
+        Some("tsv") => parse_formatted_value_as(input, FormattedValueType::Tsv),
+        _ => return parse_formatted_value(input),
+    };
+    table.unwrap_or_else(|_| parse_formatted_value(input))
+}
 
+/// Guess the type of the content itself, without resolving it as a file, url or command.
+pub fn guess_formatted_value(input: &str) -> FormattedValue {
     if let Ok(value) = serde_json::from_str(input) {
         return FormattedValue::Json(value);
     }
     if let Ok(values) = guess_jsonl(input) {
         return FormattedValue::Jsonl(values);
     }
-
-    // if let Ok(value) = serde_yaml::from_str(input) {
-    //     return FormattedValue::Yaml(value);
-    // }
+    if input.trim_start().starts_with('<') && xml::validate(input).is_ok() {
+        return FormattedValue::Xml(input.to_string());
+    }
     if let Ok(value) = toml::from_str(input) {
         return FormattedValue::Toml(value);
     }
+    if let Ok(mut values) = guess_yaml(input) {
+        return if values.len() == 1 {
+            FormattedValue::Yaml(values.remove(0))
+        } else {
+            FormattedValue::YamlStream(values)
+        };
+    }
+    if let Ok(table) = Table::guess(input) {
+        return FormattedValue::Csv(table);
+    }
     FormattedValue::Text(input.to_string())
 }
 
 fn guess_jsonl(input: &str) -> crate::Result<Vec<Value>> {
     let mut values = Vec::new();
     for (index, line) in input.lines().enumerate() {
+        let indent = line.len() - line.trim_start().len();
         let line = line.trim();
         if line.is_empty() {
             continue;
         }
-        let value = serde_json::from_str(line)
-            .map_err(|error| anyhow!("Invalid JSONL at line {}: {}", index + 1, error))?;
+        let value = serde_json::from_str(line).map_err(|error| {
+            located_error(
+                "JSONL",
+                error.to_string(),
+                index + 1,
+                indent + error.column(),
+            )
+        })?;
         values.push(value);
     }
     Ok(values)
 }
 
+/// Parse the input as `type_` only, instead of guessing. Errors carry the line
+/// and column where parsing failed.
+pub fn parse_formatted_value_as(
+    input: &str,
+    type_: FormattedValueType,
+) -> crate::Result<FormattedValue> {
+    match type_ {
+        FormattedValueType::Json => serde_json::from_str(input)
+            .map(FormattedValue::Json)
+            .map_err(|err| located_error("JSON", err.to_string(), err.line(), err.column())),
+        FormattedValueType::Jsonl => guess_jsonl(input).map(FormattedValue::Jsonl),
+        FormattedValueType::Yaml | FormattedValueType::YamlStream => {
+            let mut values = vec![];
+            for document in serde_yaml::Deserializer::from_str(input) {
+                let value = serde_yaml::Value::deserialize(document).map_err(|err| {
+                    let (line, column) = err
+                        .location()
+                        .map(|it| (it.line(), it.column()))
+                        .unwrap_or((1, 1));
+                    located_error("YAML", err.to_string(), line, column)
+                })?;
+                if !value.is_null() {
+                    values.push(value);
+                }
+            }
+            match (type_, values.len()) {
+                (FormattedValueType::Yaml, 0) => Ok(FormattedValue::Yaml(serde_yaml::Value::Null)),
+                (FormattedValueType::Yaml, 1) => Ok(FormattedValue::Yaml(values.remove(0))),
+                _ => Ok(FormattedValue::YamlStream(values)),
+            }
+        }
+        FormattedValueType::Toml => {
+            toml::from_str(input)
+                .map(FormattedValue::Toml)
+                .map_err(|err: toml::de::Error| {
+                    let (line, column) = err
+                        .span()
+                        .map(|it| line_column(input, it.start))
+                        .unwrap_or((1, 1));
+                    located_error("TOML", err.message().to_string(), line, column)
+                })
+        }
+        FormattedValueType::Csv | FormattedValueType::Tsv => {
+            // the delimiter is forced, it is only sniffed when the type is guessed
+            let name = if type_ == FormattedValueType::Tsv {
+                "TSV"
+            } else {
+                "CSV"
+            };
+            read_csv_rows(input, type_.delimiter(), None)
+                .map(|rows| FormattedValue::Csv(Table::with_rows(type_.delimiter(), rows)))
+                .map_err(|err| anyhow!("Invalid {name}, {err}"))
+        }
+        FormattedValueType::Xml => {
+            xml::validate(input).map(|_| FormattedValue::Xml(input.to_string()))
+        }
+        FormattedValueType::Text => Ok(FormattedValue::Text(input.to_string())),
+    }
+}
+
+/// 1-based line and column of a byte offset.
+pub(crate) fn line_column(input: &str, offset: usize) -> (usize, usize) {
+    let mut offset = offset.min(input.len());
+    while !input.is_char_boundary(offset) {
+        offset -= 1;
+    }
+    let before = &input[..offset];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|it| *it != '\n').count() + 1;
+    (line, column)
+}
+
+/// Parsers usually end messages with the location, eg. serde_json's
+/// `... at line 1 column 5`, which is dropped to report it only once.
+pub(crate) fn located_error(
+    type_name: &str,
+    message: String,
+    line: usize,
+    column: usize,
+) -> anyhow::Error {
+    let message = message
+        .split(" at line ")
+        .next()
+        .unwrap_or_default()
+        .trim()
+        .to_string();
+    anyhow!("Invalid {type_name} at line {line}, column {column}: {message}")
+}
+
+/// YAML accepts almost any text as a plain scalar, so only multi-line documents
+/// whose top level is a mapping or a sequence are treated as YAML; otherwise a
+/// line like `Note: something` would be detected as a one-key mapping.
+fn guess_yaml(input: &str) -> crate::Result<Vec<serde_yaml::Value>> {
+    let lines = input
+        .lines()
+        .map(|it| it.trim())
+        .filter(|it| !it.is_empty() && !it.starts_with('#'))
+        .collect::<Vec<_>>();
+    if lines.len() < 2 {
+        return Err(anyhow!("Not a valid YAML document"));
+    }
+    let mut values = Vec::new();
+    for document in serde_yaml::Deserializer::from_str(input) {
+        let value = serde_yaml::Value::deserialize(document)?;
+        match value {
+            serde_yaml::Value::Null => continue,
+            serde_yaml::Value::Mapping(_) | serde_yaml::Value::Sequence(_) => values.push(value),
+            _ => return Err(anyhow!("Not a valid YAML document")),
+        }
+    }
+    if values.is_empty() {
+        Err(anyhow!("Not a valid YAML document"))
+    } else {
+        Ok(values)
+    }
+}
+
+fn yaml_stream_to_string(values: &[serde_yaml::Value]) -> crate::Result<String> {
+    let mut string = String::new();
+    for value in values {
+        string.push_str("---\n");
+        string.push_str(&serde_yaml::to_string(value)?);
+    }
+    Ok(string)
+}
+
+const CSV_DELIMITERS: [u8; 4] = [b',', b'\t', b';', b'|'];
+
+impl FormattedValueType {
+    /// The delimiter a table of the type is read and written with.
+    fn delimiter(self) -> u8 {
+        match self {
+            FormattedValueType::Tsv => b'\t',
+            _ => b',',
+        }
+    }
+}
+const CSV_SNIFF_RECORDS: usize = 20;
+
+/// A delimited table (CSV, TSV, ...). Fields are kept as text, they are only
+/// typed when rows are turned into json records.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Table {
+    pub delimiter: u8,
+    pub headers: Option<Vec<String>>,
+    pub rows: Vec<Vec<String>>,
+}
+
+impl Table {
+    /// Parse delimited text, the delimiter is sniffed from comma, tab,
+    /// semicolon and pipe; at least two rows with the same number (> 1) of
+    /// fields are required.
+    pub fn parse(input: &str) -> crate::Result<Self> {
+        let delimiter = CSV_DELIMITERS
+            .into_iter()
+            .filter_map(|delimiter| {
+                let fields = read_csv_rows(input, delimiter, Some(CSV_SNIFF_RECORDS)).ok()?;
+                match fields.as_slice() {
+                    [first, _, ..] if first.len() > 1 => Some((first.len(), delimiter)),
+                    _ => None,
+                }
+            })
+            .rev()
+            .max_by_key(|(fields, _)| *fields)
+            .map(|(_, delimiter)| delimiter)
+            .ok_or_else(|| anyhow!("Not a valid CSV document"))?;
+        Ok(Self::with_rows(
+            delimiter,
+            read_csv_rows(input, delimiter, None)?,
+        ))
+    }
+
+    /// Parse text that is only guessed to be a table. Prose like `Hello, world.`
+    /// also has the same number of fields on each line, so fields padded after
+    /// the delimiter are only taken for a table under a header of names.
+    pub fn guess(input: &str) -> crate::Result<Self> {
+        let table = Self::parse(input)?;
+        let first = table.headers.as_ref().or(table.rows.first());
+        let padded = first
+            .into_iter()
+            .chain(&table.rows)
+            .any(|row| row.iter().skip(1).any(|it| it.starts_with(' ')));
+        if padded && !first.is_some_and(|it| is_csv_names(it)) {
+            return Err(anyhow!("Not a valid CSV document"));
+        }
+        Ok(table)
+    }
+
+    fn with_rows(delimiter: u8, mut rows: Vec<Vec<String>>) -> Self {
+        let headers = if rows.first().is_some_and(|it| is_csv_header(it)) {
+            Some(rows.remove(0))
+        } else {
+            None
+        };
+        Self {
+            delimiter,
+            headers,
+            rows,
+        }
+    }
+
+    /// Rows as json objects keyed by header, or as arrays when there are no
+    /// headers. Numbers and booleans are typed, empty fields become null.
+    pub fn to_records(&self) -> Vec<Value> {
+        self.rows
+            .iter()
+            .map(|row| match &self.headers {
+                Some(headers) => Value::Object(
+                    headers
+                        .iter()
+                        .zip(row)
+                        .map(|(header, field)| (header.to_string(), csv_field_to_value(field)))
+                        .collect(),
+                ),
+                None => Value::Array(row.iter().map(|it| csv_field_to_value(it)).collect()),
+            })
+            .collect()
+    }
+
+    /// An array of objects becomes rows under the union of their keys, an
+    /// array of arrays or scalars becomes rows without headers.
+    pub fn from_value(value: &Value, delimiter: u8) -> crate::Result<Self> {
+        let values = match value {
+            Value::Array(values) => values.as_slice(),
+            Value::Object(_) => std::slice::from_ref(value),
+            _ => return Err(anyhow!("Can not convert {value} to CSV")),
+        };
+        let (headers, rows) = if values.iter().all(Value::is_object) && !values.is_empty() {
+            let mut headers: Vec<String> = vec![];
+            for key in values
+                .iter()
+                .filter_map(Value::as_object)
+                .flat_map(|it| it.keys())
+            {
+                if !headers.contains(key) {
+                    headers.push(key.to_string());
+                }
+            }
+            let rows = values
+                .iter()
+                .map(|value| {
+                    headers
+                        .iter()
+                        .map(|header| {
+                            value
+                                .get(header)
+                                .map(csv_value_to_field)
+                                .unwrap_or_default()
+                        })
+                        .collect()
+                })
+                .collect();
+            (Some(headers), rows)
+        } else if values.iter().any(Value::is_object) {
+            return Err(anyhow!("Can not convert a mixed array to CSV"));
+        } else {
+            let rows = values
+                .iter()
+                .map(|value| match value {
+                    Value::Array(fields) => fields.iter().map(csv_value_to_field).collect(),
+                    value => vec![csv_value_to_field(value)],
+                })
+                .collect();
+            (None, rows)
+        };
+        Ok(Self {
+            delimiter,
+            headers,
+            rows,
+        })
+    }
+
+    pub fn to_text(&self) -> crate::Result<String> {
+        let mut writer = csv::WriterBuilder::new()
+            .delimiter(self.delimiter)
+            .flexible(true)
+            .from_writer(vec![]);
+        if let Some(headers) = &self.headers {
+            writer.write_record(headers)?;
+        }
+        for row in &self.rows {
+            writer.write_record(row)?;
+        }
+        Ok(String::from_utf8(writer.into_inner()?)?)
+    }
+}
+
+fn read_csv_rows(
+    input: &str,
+    delimiter: u8,
+    limit: Option<usize>,
+) -> crate::Result<Vec<Vec<String>>> {
+    let mut reader = csv::ReaderBuilder::new()
+        .delimiter(delimiter)
+        .has_headers(false)
+        .from_reader(input.as_bytes());
+    let mut rows = vec![];
+    for record in reader.records().take(limit.unwrap_or(usize::MAX)) {
+        rows.push(record?.iter().map(|it| it.to_string()).collect());
+    }
+    Ok(rows)
+}
+
+/// The first row is a header when its fields are distinct non-empty names.
+fn is_csv_header(row: &[String]) -> bool {
+    let mut names = std::collections::HashSet::new();
+    row.iter()
+        .all(|it| csv_field_to_value(it).is_string() && names.insert(it.as_str()))
+}
+
+/// Fields like `id`, `first name` or `e-mail`, no sentences.
+fn is_csv_names(row: &[String]) -> bool {
+    row.iter().all(|it| {
+        let name = it.trim();
+        !name.is_empty()
+            && name
+                .chars()
+                .all(|it| it.is_alphanumeric() || matches!(it, '_' | '-' | ' '))
+    })
+}
+
+fn csv_field_to_value(field: &str) -> Value {
+    match field {
+        "" => Value::Null,
+        "true" => Value::Bool(true),
+        "false" => Value::Bool(false),
+        // only numbers printed back as written, `1.10`, `1e3` or long ids stay strings
+        field => match serde_json::from_str::<serde_json::Number>(field) {
+            Ok(number) if number.to_string() == field => Value::Number(number),
+            _ => Value::String(field.to_string()),
+        },
+    }
+}
+
+fn csv_value_to_field(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
 #[cfg(test)]
 mod tests {
-    use super::{FormattedValue, FormattedValueType, parse_formatted_value};
+    use super::{
+        FormattedValue, FormattedValueType, Json, parse_formatted_value, parse_formatted_value_as,
+    };
     use serde_json::json;
 
     #[test]
@@ -265,4 +742,186 @@ null
         let value = parse_formatted_value("name = \"devkit\"");
         assert!(matches!(value, FormattedValue::Toml(_)));
     }
+
+    #[test]
+    fn parses_yaml_mapping_documents() {
+        let input = "apiVersion: v1\nkind: ConfigMap\ndata:\n  name: devkit\n";
+        match parse_formatted_value(input) {
+            FormattedValue::Yaml(value) => {
+                let value = serde_json::to_value(value).unwrap();
+                assert_eq!(
+                    value,
+                    json!({"apiVersion": "v1", "kind": "ConfigMap", "data": {"name": "devkit"}})
+                );
+            }
+            _ => panic!("expected Yaml"),
+        }
+    }
+
+    #[test]
+    fn parses_multi_document_yaml_into_an_array() {
+        let input = "---\nkind: Service\n---\nkind: Deployment\n";
+        let value = parse_formatted_value(input);
+        assert_eq!(value.type_(), FormattedValueType::YamlStream);
+        let converted = value.convert(FormattedValueType::Json).unwrap();
+        assert!(matches!(
+            converted,
+            FormattedValue::Json(value) if value == json!([{"kind": "Service"}, {"kind": "Deployment"}])
+        ));
+    }
+
+    #[test]
+    fn keeps_single_line_and_scalar_text_out_of_yaml() {
+        assert!(matches!(
+            parse_formatted_value("Note: this is plain text"),
+            FormattedValue::Text(_)
+        ));
+        assert!(matches!(
+            parse_formatted_value("first line of text\nsecond line of text"),
+            FormattedValue::Text(_)
+        ));
+    }
+
+    #[test]
+    fn parses_csv_with_sniffed_delimiter_and_quoted_fields() {
+        let input = "id;name;note\n1;devkit;\"a; b\"\n2;dk;\n";
+        let value = parse_formatted_value(input);
+        assert_eq!(value.type_(), FormattedValueType::Csv);
+        let names = Json::query(&value, Some("$[*].name"), None).unwrap();
+        assert!(matches!(names, FormattedValue::Json(value) if value == json!(["devkit", "dk"])));
+        let records = value.into_records().unwrap();
+        assert_eq!(
+            records,
+            vec![
+                json!({"id": 1, "name": "devkit", "note": "a; b"}),
+                json!({"id": 2, "name": "dk", "note": null})
+            ]
+        );
+        let tsv = parse_formatted_value("1\t2\t3\n4\t5\t6");
+        assert!(
+            matches!(tsv, FormattedValue::Csv(ref table) if table.delimiter == b'\t' && table.headers.is_none())
+        );
+        let padded = parse_formatted_value("name, age\nbob, 3\n");
+        assert_eq!(padded.type_(), FormattedValueType::Csv);
+    }
+
+    #[test]
+    fn keeps_comma_prose_as_text() {
+        for input in [
+            "Hello, world.\nThanks, bye.",
+            "Dear team, thanks.\nSee you, soon.\nBest, dk",
+        ] {
+            assert_eq!(
+                parse_formatted_value(input).type_(),
+                FormattedValueType::Text,
+                "{input}"
+            );
+        }
+        let forced =
+            parse_formatted_value_as("Hello, world.\nThanks, bye.", FormattedValueType::Csv);
+        assert_eq!(forced.unwrap().type_(), FormattedValueType::Csv);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("notes.csv");
+        std::fs::write(&path, "Hello, world.\nThanks, bye.").unwrap();
+        let value = parse_formatted_value(path.to_str().unwrap());
+        assert_eq!(value.type_(), FormattedValueType::Csv);
+    }
+
+    #[test]
+    fn keeps_csv_fields_that_are_not_plain_numbers_as_strings() {
+        let records =
+            parse_formatted_value("version,id,n\n1.10,12345678901234567890123,1e3\n2,-7,0.5\n")
+                .into_records()
+                .unwrap();
+        assert_eq!(
+            records,
+            vec![
+                json!({"version": "1.10", "id": "12345678901234567890123", "n": "1e3"}),
+                json!({"version": 2, "id": -7, "n": 0.5})
+            ]
+        );
+    }
+
+    #[test]
+    fn converts_json_array_of_objects_to_csv_and_back() {
+        let value = FormattedValue::Json(json!([
+            {"id": 1, "name": "a,b"},
+            {"id": 2, "tags": ["x"]}
+        ]));
+        let csv = value.convert(FormattedValueType::Csv).unwrap();
+        let text = csv.to_string().unwrap();
+        assert_eq!(text, "id,name,tags\n1,\"a,b\",\n2,,\"[\"\"x\"\"]\"\n");
+        let tsv = csv.clone().convert(FormattedValueType::Tsv).unwrap();
+        assert_eq!(tsv.type_(), FormattedValueType::Tsv);
+        assert!(
+            tsv.to_string()
+                .unwrap()
+                .starts_with("id\tname\ttags\n1\ta,b\t\n")
+        );
+        assert!(matches!(
+            parse_formatted_value(&text).convert(FormattedValueType::Json).unwrap(),
+            FormattedValue::Json(value) if value == json!([
+                {"id": 1, "name": "a,b", "tags": null},
+                {"id": 2, "name": null, "tags": "[\"x\"]"}
+            ])
+        ));
+    }
+
+    #[test]
+    fn parses_xml_documents_before_falling_back_to_text() {
+        let value =
+            parse_formatted_value("<?xml version=\"1.0\"?>\n<note id=\"1\"><to>dk</to></note>");
+        assert_eq!(value.type_(), FormattedValueType::Xml);
+        assert!(matches!(
+            value.convert(FormattedValueType::Json).unwrap(),
+            FormattedValue::Json(value) if value == json!({"note": {"@id": "1", "to": "dk"}})
+        ));
+        assert!(matches!(
+            parse_formatted_value("<p>unclosed"),
+            FormattedValue::Text(_)
+        ));
+    }
+
+    #[test]
+    fn forces_the_parser_of_the_given_type() {
+        let value = parse_formatted_value_as("key = \"v\"", FormattedValueType::Text).unwrap();
+        assert!(matches!(value, FormattedValue::Text(_)));
+        let value = parse_formatted_value_as("{\"a\":1}", FormattedValueType::Jsonl).unwrap();
+        assert!(matches!(value, FormattedValue::Jsonl(values) if values == vec![json!({"a": 1})]));
+        let tsv =
+            parse_formatted_value_as("name\tnote\ndk\ta, b, c\n", FormattedValueType::Tsv).unwrap();
+        assert_eq!(tsv.type_(), FormattedValueType::Tsv);
+        assert_eq!(
+            tsv.into_records().unwrap(),
+            vec![json!({"name": "dk", "note": "a, b, c"})]
+        );
+        let csv = parse_formatted_value_as("a\tb,c\n1\t2,3\n", FormattedValueType::Csv).unwrap();
+        assert_eq!(
+            csv.into_records().unwrap(),
+            vec![json!({"a\tb": "1\t2", "c": 3})]
+        );
+    }
+
+    #[test]
+    fn reports_line_and_column_of_forced_parse_errors() {
+        let err = parse_formatted_value_as("{\n  \"a\": 1,\n  b\n}", FormattedValueType::Json)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid JSON at line 3, column 3: key must be a string"
+        );
+        let err = parse_formatted_value_as("{\"a\":1}\n  {\"b\":}", FormattedValueType::Jsonl)
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid JSONL at line 2, column 8: expected value"
+        );
+        let err = parse_formatted_value_as("a = 1\nb = ", FormattedValueType::Toml).unwrap_err();
+        assert!(
+            err.to_string()
+                .starts_with("Invalid TOML at line 2, column")
+        );
+        let err = parse_formatted_value_as("<a>\n<b></a>", FormattedValueType::Xml).unwrap_err();
+        assert!(err.to_string().starts_with("Invalid XML at line 2"));
+    }
 }
@@ -0,0 +1,203 @@
+use super::print_response;
+use crate::command::http_parser::{self, Har, HarEntry, HttpClientOptions};
+use anyhow::anyhow;
+use regex::Regex;
+use std::path::PathBuf;
+use std::str::FromStr;
+
+#[derive(clap::Args)]
+pub struct HarArgs {
+    #[arg(help = "HTTP Archive exported by the browser devtools")]
+    file: PathBuf,
+    #[arg(short, long, help = "only entries whose url matches the regex")]
+    url: Option<String>,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "only entries with these statuses, e.g. 404,5xx"
+    )]
+    status: Vec<StatusFilter>,
+    #[arg(short, long, help = "only entries with this method")]
+    method: Option<String>,
+    #[arg(
+        short,
+        long,
+        value_delimiter = ',',
+        help = "only the entries at these 1-based indexes of the listing"
+    )]
+    entry: Vec<usize>,
+    #[arg(long, help = "print the entries as requests: http, curl")]
+    export: Option<HarExport>,
+    #[arg(long, help = "send the entries again and print their responses")]
+    replay: bool,
+}
+
+/// A status, or a class of them as `4xx`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StatusFilter {
+    Exact(u16),
+    Class(u16),
+}
+
+impl FromStr for StatusFilter {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim().to_lowercase();
+        match s.strip_suffix("xx") {
+            Some(class) if matches!(class, "1" | "2" | "3" | "4" | "5") => {
+                Ok(StatusFilter::Class(class.parse()?))
+            }
+            Some(_) => Err(anyhow!("Invalid status class: {s}, expected 1xx to 5xx")),
+            None => s
+                .parse()
+                .map(StatusFilter::Exact)
+                .map_err(|_| anyhow!("Invalid status: {s}")),
+        }
+    }
+}
+
+impl StatusFilter {
+    fn matches(&self, status: u16) -> bool {
+        match self {
+            StatusFilter::Exact(it) => *it == status,
+            StatusFilter::Class(class) => status / 100 == *class,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum HarExport {
+    Http,
+    Curl,
+}
+
+impl FromStr for HarExport {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "http" => Ok(HarExport::Http),
+            "curl" => Ok(HarExport::Curl),
+            _ => Err(anyhow!("Invalid export format: {s}, expected http or curl")),
+        }
+    }
+}
+
+impl HarArgs {
+    /// The entries passing every filter, with their 1-based index in the file.
+    fn select<'a>(&self, har: &'a Har) -> crate::Result<Vec<(usize, &'a HarEntry)>> {
+        let url = self.url.as_deref().map(Regex::new).transpose()?;
+        Ok(har
+            .entries
+            .iter()
+            .enumerate()
+            .map(|(idx, entry)| (idx + 1, entry))
+            .filter(|(idx, entry)| {
+                (self.entry.is_empty() || self.entry.contains(idx))
+                    && url
+                        .as_ref()
+                        .is_none_or(|it| it.is_match(&entry.request.url))
+                    && self
+                        .method
+                        .as_ref()
+                        .is_none_or(|it| it.eq_ignore_ascii_case(&entry.request.method))
+                    && (self.status.is_empty()
+                        || self
+                            .status
+                            .iter()
+                            .any(|it| it.matches(entry.response.status)))
+            })
+            .collect())
+    }
+}
+
+impl HarArgs {
+    pub fn run(&self, options: &HttpClientOptions) -> crate::Result<()> {
+        let har = Har::load(&self.file)?;
+        let selected = self.select(&har)?;
+        if self.replay {
+            let mut failed = 0;
+            for (idx, entry) in &selected {
+                let request = entry.to_request();
+                println!(
+                    "### {idx} {}\n{} {}",
+                    request.name().unwrap_or_default(),
+                    request.method(),
+                    request.url()
+                );
+                let response = reqwest::Request::try_from(&**request)
+                    .and_then(|it| http_parser::send(it, options));
+                match response {
+                    Ok(response) => {
+                        print_response(&response)?;
+                        let recorded = entry.response.status;
+                        if recorded != 0 && recorded != response.status.as_u16() {
+                            eprintln!(
+                                "request {idx} responded {}, recorded {recorded}",
+                                response.status.as_u16()
+                            );
+                        }
+                    }
+                    Err(err) => {
+                        failed += 1;
+                        eprintln!("request {idx} failed: {err:#}");
+                    }
+                }
+                println!();
+            }
+            return if failed > 0 {
+                Err(anyhow!("{failed} of {} requests failed", selected.len()))
+            } else {
+                Ok(())
+            };
+        }
+        match self.export {
+            Some(HarExport::Http) => {
+                let requests: Vec<_> = selected
+                    .iter()
+                    .map(|(_, entry)| entry.to_request().to_http())
+                    .collect();
+                println!("{}", requests.join("\n\n"));
+            }
+            Some(HarExport::Curl) => {
+                let mut commands = vec![];
+                for (idx, entry) in &selected {
+                    let request = entry.to_request();
+                    commands.push(format!(
+                        "# {idx} {}\n{}",
+                        request.name().unwrap_or_default(),
+                        request.to_curl()?
+                    ));
+                }
+                println!("{}", commands.join("\n\n"));
+            }
+            None => {
+                for (idx, entry) in &selected {
+                    println!(
+                        "{idx:>3}  {:<6} {:>3} {:>6.0} ms  {}",
+                        entry.request.method, entry.response.status, entry.time, entry.request.url
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatusFilter;
+
+    #[test]
+    fn parses_status_filters() {
+        let filter = |s: &str| s.parse::<StatusFilter>();
+        assert_eq!(filter("404").unwrap(), StatusFilter::Exact(404));
+        assert!(filter("4XX").unwrap().matches(451));
+        assert!(!filter("4xx").unwrap().matches(500));
+        assert!(filter("0").unwrap().matches(0));
+        assert!(filter("6xx").is_err());
+        assert!(filter("ok").is_err());
+    }
+}
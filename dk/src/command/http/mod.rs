@@ -0,0 +1,486 @@
+use super::StringInput;
+use super::formatter::{FormattedValue, guess_formatted_value};
+use super::http_parser::{
+    self, Environment, HttpRequest, HttpResponse, JetBrainsHttp, Outcome, RawHttpMessage,
+};
+use super::uri::{Uri, UriComponent, UriComponentValue};
+use anyhow::{Context, anyhow};
+use itertools::Itertools;
+use std::fs;
+use std::io::{self, IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use url::Url;
+
+mod har;
+
+pub use super::http_parser::HttpClientOptions;
+
+#[derive(clap::Subcommand)]
+pub enum HttpCommand {
+    #[clap(about = "list the requests of a .http file, alias 'ls'", alias = "ls")]
+    List {
+        #[arg(help = "jetbrains .http file")]
+        file: PathBuf,
+    },
+    #[clap(
+        about = "run the requests of a .http file, all of them in sequence if none is selected, and check their `>` response handlers, alias 'r'",
+        alias = "r"
+    )]
+    Run {
+        #[arg(help = "jetbrains .http file")]
+        file: PathBuf,
+        #[arg(help = "request to run, by `### name` or 1-based index")]
+        request: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "environment of http-client.env.json and http-client.private.env.json next to the file"
+        )]
+        env: Option<String>,
+    },
+    #[clap(
+        about = "send one request, a url, jetbrains http request or curl command, and print the whole response, alias 's'",
+        alias = "s"
+    )]
+    Send {
+        #[arg(
+            help = "url, jetbrains http request or curl command",
+            default_value = ""
+        )]
+        request: StringInput,
+    },
+    #[clap(
+        about = "parse raw http/1.x requests and responses captured by wireshark, burp or nc, alias 'p'",
+        alias = "p"
+    )]
+    Parse {
+        #[arg(help = "file of the raw messages, or the messages, read from stdin if empty")]
+        input: Option<String>,
+        #[arg(long, help = "print requests as .http requests")]
+        http: bool,
+    },
+    #[clap(
+        about = "serve canned responses of a .http-like routes file, or a directory of json files mapped by path"
+    )]
+    Mock {
+        #[arg(
+            help = "routes file, `METHOD /path` and the response per `###` block, or a directory of json files"
+        )]
+        source: PathBuf,
+        #[arg(short, long, default_value_t = 8080, help = "port to listen on")]
+        port: u16,
+        #[arg(long, default_value = "127.0.0.1", help = "address to listen on")]
+        host: String,
+    },
+    #[clap(
+        about = "list the entries of a .har file, filter them, export them as .http requests or curl commands, or replay them"
+    )]
+    Har(har::HarArgs),
+    #[clap(about = "convert requests of a .http file to curl commands")]
+    ToCurl {
+        #[arg(help = "jetbrains .http file")]
+        file: PathBuf,
+        #[arg(help = "request to convert, by `### name` or 1-based index")]
+        request: Option<String>,
+        #[arg(
+            short,
+            long,
+            help = "resolve variables with the environment of http-client.env.json next to the file"
+        )]
+        env: Option<String>,
+    },
+    #[clap(about = "convert a curl command to a .http request")]
+    FromCurl {
+        #[arg(help = "curl command", default_value = "")]
+        curl: StringInput,
+        #[arg(short, long, help = "`### name` of the request")]
+        name: Option<String>,
+    },
+}
+
+/// Options of the http client, over the `http.toml` config file, shared by
+/// every command sending http requests.
+#[derive(clap::Args, Debug, Default)]
+pub struct HttpClientArgs {
+    #[arg(long, global = true, help = "timeout of each http request in seconds")]
+    timeout: Option<f64>,
+    #[arg(
+        long,
+        global = true,
+        help = "proxy of http requests, e.g. http://proxy:3128, socks5://proxy:1080"
+    )]
+    proxy: Option<String>,
+    #[arg(
+        long,
+        global = true,
+        help = "PEM file of CA certificates to trust besides the built-in ones"
+    )]
+    cacert: Option<PathBuf>,
+    #[arg(
+        long,
+        global = true,
+        help = "accept invalid tls certificates and host names"
+    )]
+    insecure: bool,
+    #[arg(
+        long,
+        global = true,
+        help = "retry http requests failing to connect, timing out or responding 429/502/503/504, with exponential backoff"
+    )]
+    retries: Option<u32>,
+    #[arg(
+        long,
+        global = true,
+        help = "read the body of non-2xx http responses instead of failing"
+    )]
+    allow_error_status: bool,
+}
+
+impl HttpClientArgs {
+    /// Options of the config file, overridden by the given flags.
+    pub fn options(&self) -> crate::Result<HttpClientOptions> {
+        let mut options = HttpClientOptions::load_config()?;
+        let Self {
+            timeout,
+            proxy,
+            cacert,
+            insecure,
+            retries,
+            allow_error_status,
+        } = self;
+        options.timeout = timeout.or(options.timeout);
+        options.proxy = proxy.clone().or(options.proxy);
+        options.cacert = cacert.clone().or(options.cacert);
+        options.insecure |= insecure;
+        options.retries = retries.unwrap_or(options.retries);
+        options.allow_error_status |= allow_error_status;
+        Ok(options)
+    }
+}
+
+impl HttpCommand {
+    /// Run the command, every request is sent with `options`.
+    pub fn run(&self, options: &HttpClientOptions) -> crate::Result<()> {
+        match self {
+            HttpCommand::List { file } => {
+                for (idx, request) in JetBrainsHttp::parse_file(file)?.iter().enumerate() {
+                    println!(
+                        "{:>3}  {:<24} {} {}",
+                        idx + 1,
+                        request.name().unwrap_or("-"),
+                        request.method(),
+                        request.url()
+                    );
+                }
+                Ok(())
+            }
+            HttpCommand::Run { file, request, env } => {
+                let requests = JetBrainsHttp::parse_file(file)?;
+                let mut env = load_env(file, env.as_deref())?;
+                let selected = select(&requests, request.as_deref())?;
+                let mut failed = 0;
+                for (idx, request) in &selected {
+                    println!(
+                        "### {} {}\n{} {}",
+                        idx + 1,
+                        request.name().unwrap_or_default(),
+                        request.method(),
+                        request.url()
+                    );
+                    let outcomes =
+                        execute(request, &env, options).and_then(|(request, response)| {
+                            print_response(&response)?;
+                            request.handle(&response, &mut env)
+                        });
+                    match outcomes {
+                        Ok(outcomes) => {
+                            if !outcomes.is_empty() {
+                                println!();
+                            }
+                            for outcome in &outcomes {
+                                println!("{outcome}");
+                            }
+                            failed += usize::from(outcomes.iter().any(Outcome::is_failed));
+                        }
+                        Err(err) => {
+                            failed += 1;
+                            eprintln!("request {} failed: {err:#}", idx + 1);
+                        }
+                    }
+                    println!();
+                }
+                if failed > 0 {
+                    Err(anyhow!("{failed} of {} requests failed", selected.len()))
+                } else {
+                    Ok(())
+                }
+            }
+            HttpCommand::Send { request } => {
+                let response = HttpRequest::from_str(request)?.send(options)?;
+                print_response(&response)
+            }
+            HttpCommand::Parse { input, http } => {
+                let input = match input {
+                    Some(input) if Path::new(input).is_file() => {
+                        fs::read(input).with_context(|| format!("read file {input} failed"))?
+                    }
+                    Some(input) => input.as_bytes().to_vec(),
+                    None if io::stdin().is_terminal() => {
+                        return Err(anyhow!("No input, give a file or pipe the messages"));
+                    }
+                    None => {
+                        let mut input = vec![];
+                        io::stdin().read_to_end(&mut input)?;
+                        input
+                    }
+                };
+                let messages = RawHttpMessage::parse_all(&input)?
+                    .iter()
+                    .map(|it| print_message(it, *http))
+                    .collect::<crate::Result<Vec<_>>>()?;
+                println!("{}", messages.join("\n\n"));
+                Ok(())
+            }
+            HttpCommand::Mock { source, port, host } => {
+                http_parser::serve_mock(source, &format!("{host}:{port}"))
+            }
+            HttpCommand::Har(args) => args.run(options),
+            HttpCommand::ToCurl { file, request, env } => {
+                let requests = JetBrainsHttp::parse_file(file)?;
+                let selected = select(&requests, request.as_deref())?;
+                let env = env
+                    .as_deref()
+                    .map(|env| load_env(file, Some(env)))
+                    .transpose()?;
+                let mut commands = vec![];
+                for (idx, request) in &selected {
+                    let curl = match &env {
+                        Some(env) => request.resolve(env)?.to_curl()?,
+                        None => request.to_curl()?,
+                    };
+                    commands.push(if selected.len() > 1 {
+                        format!(
+                            "# {} {}\n{curl}",
+                            idx + 1,
+                            request.name().unwrap_or_default()
+                        )
+                    } else {
+                        curl
+                    });
+                }
+                println!("{}", commands.join("\n\n"));
+                Ok(())
+            }
+            HttpCommand::FromCurl { curl, name } => {
+                let mut request = JetBrainsHttp::from_curl(curl)?;
+                if let Some(name) = name {
+                    request = request.with_name(name);
+                }
+                println!("{}", request.to_http());
+                Ok(())
+            }
+        }
+    }
+}
+
+fn load_env(file: &Path, env: Option<&str>) -> crate::Result<Environment> {
+    match env {
+        Some(env) => Environment::load(
+            file.parent()
+                .filter(|it| !it.as_os_str().is_empty())
+                .unwrap_or(Path::new(".")),
+            env,
+        ),
+        None => Ok(Environment::default()),
+    }
+}
+
+/// Select the request named `selector`, or at its 1-based index; all of them if `None`.
+fn select<'a>(
+    requests: &'a [JetBrainsHttp],
+    selector: Option<&str>,
+) -> crate::Result<Vec<(usize, &'a JetBrainsHttp)>> {
+    let Some(selector) = selector else {
+        return Ok(requests.iter().enumerate().collect());
+    };
+    if let Some(position) = requests
+        .iter()
+        .position(|it| it.name() == Some(selector))
+        .or_else(|| {
+            selector
+                .parse::<usize>()
+                .ok()
+                .filter(|it| (1..=requests.len()).contains(it))
+                .map(|it| it - 1)
+        })
+    {
+        Ok(vec![(position, &requests[position])])
+    } else {
+        Err(anyhow!(
+            "No request '{selector}', available: {}",
+            requests
+                .iter()
+                .enumerate()
+                .map(|(idx, it)| it.name().map_or((idx + 1).to_string(), str::to_string))
+                .join(", ")
+        ))
+    }
+}
+
+/// Send the request with its variables resolved, the resolved request is returned
+/// to apply its response handlers.
+fn execute(
+    request: &JetBrainsHttp,
+    env: &Environment,
+    options: &HttpClientOptions,
+) -> crate::Result<(JetBrainsHttp, HttpResponse)> {
+    let request = request.resolve(env)?;
+    let response = http_parser::send(reqwest::Request::try_from(&**request)?, options)?;
+    Ok((request, response))
+}
+
+fn print_response(response: &HttpResponse) -> crate::Result<()> {
+    println!("{response}");
+    if !response.body.is_empty() {
+        println!("\n{}", format_body(&response.body)?);
+    }
+    Ok(())
+}
+
+fn format_body(body: &str) -> crate::Result<String> {
+    match guess_formatted_value(body) {
+        FormattedValue::Text(text) => Ok(text),
+        value => value.to_string_pretty(),
+    }
+}
+
+/// The start line and headers, the decoded query parameters of requests, then the formatted body.
+fn print_message(message: &RawHttpMessage, http: bool) -> crate::Result<String> {
+    let mut lines = vec![];
+    match message {
+        RawHttpMessage::Request(request) if http => return Ok(request.to_http()),
+        RawHttpMessage::Request(request) => {
+            lines.push(format!("{} {}", request.method(), request.url()));
+            lines.extend(request.headers().iter().map(|(k, v)| format!("{k}: {v}")));
+            let url = Url::parse(&request.url())?;
+            if url.query().is_some() {
+                let query = Uri::Url(url).parse(&Some(vec![UriComponent::Query(None)]))?;
+                lines.push("\nquery:".to_string());
+                for component in query {
+                    if let UriComponentValue::Query(parts) = component {
+                        lines.extend(parts.iter().map(|(k, v)| format!("   {k}={v}")));
+                    }
+                }
+            }
+        }
+        RawHttpMessage::Response(response) => {
+            lines.push(format!("{:?} {}", response.version, response.status));
+            lines.extend(
+                response
+                    .headers
+                    .iter()
+                    .map(|(k, v)| format!("{k}: {}", String::from_utf8_lossy(v.as_bytes()))),
+            );
+        }
+    }
+    let body = message.body();
+    if !body.is_empty() {
+        lines.push(String::new());
+        match message.content_headers() {
+            (_, Some(encoding)) if !encoding.eq_ignore_ascii_case("identity") => {
+                lines.push(format!(
+                    "<{} bytes, content-encoding {encoding} not decoded>",
+                    body.len()
+                ))
+            }
+            _ => lines.push(format_body(&body)?),
+        }
+    }
+    Ok(lines.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpClientOptions, execute, print_message, select};
+    use crate::command::http_parser::{Environment, JetBrainsHttp, RawHttpMessage};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+
+    const REQUESTS: &str = r#"
+### first
+GET https://example.com/1
+
+### second
+GET https://example.com/2
+
+###
+GET https://example.com/3
+"#;
+
+    #[test]
+    fn prints_non_ascii_headers_of_captures() {
+        let capture =
+            "HTTP/1.1 200 OK\r\nContent-Disposition: attachment; filename=\"résumé.pdf\"\r\n\r\n";
+        let messages = RawHttpMessage::parse_all(capture.as_bytes()).unwrap();
+        assert_eq!(
+            print_message(&messages[0], false).unwrap(),
+            "HTTP/1.1 200 OK\ncontent-disposition: attachment; filename=\"résumé.pdf\""
+        );
+    }
+
+    #[test]
+    fn selects_requests_by_name_or_index() {
+        let requests = JetBrainsHttp::parse_all(REQUESTS).unwrap();
+        let selected = |selector| {
+            select(&requests, selector)
+                .map(|it| it.into_iter().map(|(idx, _)| idx).collect::<Vec<_>>())
+        };
+        assert_eq!(selected(None).unwrap(), vec![0, 1, 2]);
+        assert_eq!(selected(Some("second")).unwrap(), vec![1]);
+        assert_eq!(selected(Some("3")).unwrap(), vec![2]);
+        let err = selected(Some("fourth")).unwrap_err().to_string();
+        assert_eq!(err, "No request 'fourth', available: first, second, 3");
+    }
+
+    #[test]
+    fn executes_against_a_local_server() {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1024];
+            let n = stream.read(&mut buf).unwrap();
+            let body = r#"{"ok":true}"#;
+            write!(
+                stream,
+                "HTTP/1.1 201 Created\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+                body.len()
+            )
+            .unwrap();
+            String::from_utf8_lossy(&buf[..n]).to_string()
+        });
+        let request = JetBrainsHttp::parse_all(&format!(
+            "@port = {port}\n### create\nPOST http://127.0.0.1:{{{{port}}}}/items\nX-Trace: {{{{$randomInt}}}}\n\n{{}}"
+        ))
+        .unwrap()
+        .remove(0);
+        let (_, response) = execute(
+            &request,
+            &Environment::default(),
+            &HttpClientOptions::default(),
+        )
+        .unwrap();
+        let received = server.join().unwrap();
+        assert!(received.starts_with("POST /items HTTP/1.1\r\n"));
+        assert!(received.contains("x-trace: "));
+        assert!(!received.contains("{{"));
+        assert_eq!(response.status.as_u16(), 201);
+        assert_eq!(response.body, r#"{"ok":true}"#);
+        assert!(
+            response.to_string().ends_with(
+                "\ncontent-type: application/json\ncontent-length: 11\nconnection: close"
+            )
+        );
+    }
+}
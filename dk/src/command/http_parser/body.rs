@@ -0,0 +1,192 @@
+use anyhow::{Context, anyhow};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `< ./payload.json`, the file is sent as is
+const FILE_INCLUDE: &str = "<";
+/// `<@ ./payload.json`, `{{variables}}` of the file are substituted
+const FILE_INCLUDE_SUBSTITUTED: &str = "<@";
+
+/// One part of a `multipart/*` body, its content lines may include files too.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MultipartPart {
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<String>,
+}
+
+/// The path of a `< path` body line, and whether its variables should be substituted.
+pub(super) fn include_path(line: &str) -> Option<(&str, bool)> {
+    if let Some(path) = line.strip_prefix(FILE_INCLUDE_SUBSTITUTED) {
+        Some((path.trim(), true))
+    } else {
+        line.strip_prefix(FILE_INCLUDE)
+            .filter(|it| it.starts_with(char::is_whitespace))
+            .map(|path| (path.trim(), false))
+    }
+}
+
+/// Relative paths are resolved against the directory of the `.http` file.
+pub(super) fn include_file(base_dir: Option<&Path>, path: &str) -> PathBuf {
+    match base_dir {
+        Some(base_dir) if Path::new(path).is_relative() => base_dir.join(path),
+        _ => PathBuf::from(path),
+    }
+}
+
+/// The `boundary` of a `multipart/*` content type.
+pub(super) fn multipart_boundary(content_type: &str) -> Option<&str> {
+    let mut params = content_type.split(';').map(str::trim);
+    if !params.next()?.to_lowercase().starts_with("multipart/") {
+        return None;
+    }
+    params
+        .filter_map(|it| it.split_once('='))
+        .find(|(key, _)| key.trim().eq_ignore_ascii_case("boundary"))
+        .map(|(_, value)| value.trim().trim_matches('"'))
+}
+
+pub(super) fn parse_multipart(
+    lines: &[String],
+    boundary: &str,
+) -> crate::Result<Vec<MultipartPart>> {
+    let delimiter = format!("--{boundary}");
+    let close_delimiter = format!("--{boundary}--");
+    let mut parts = vec![];
+    let mut part: Option<(MultipartPart, bool)> = None;
+    for line in lines {
+        let trimmed = line.trim_end();
+        if trimmed == delimiter || trimmed == close_delimiter {
+            parts.extend(part.take().map(|(it, _)| it));
+            if trimmed == close_delimiter {
+                break;
+            }
+            part = Some((
+                MultipartPart {
+                    headers: vec![],
+                    body: vec![],
+                },
+                false,
+            ));
+        } else if let Some((part, in_body)) = part.as_mut() {
+            if *in_body {
+                part.body.push(line.clone());
+            } else if line.trim().is_empty() {
+                *in_body = true;
+            } else {
+                let (name, value) = line
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid multipart part header: {line}"))?;
+                part.headers
+                    .push((name.trim().to_string(), value.trim().to_string()));
+            }
+        }
+    }
+    parts.extend(part.map(|(it, _)| it));
+    if parts.is_empty() {
+        Err(anyhow!("No multipart part found for boundary {boundary}"))
+    } else {
+        Ok(parts)
+    }
+}
+
+/// The bytes to send: `< path` lines are replaced by the file content, a
+/// multipart body is re-encoded with CRLF line breaks.
+pub(super) fn encode(
+    lines: &[String],
+    content_type: Option<&str>,
+    base_dir: Option<&Path>,
+) -> crate::Result<Vec<u8>> {
+    let Some(boundary) = content_type.and_then(multipart_boundary) else {
+        return join_lines(lines, b"\n", base_dir);
+    };
+    let mut bytes = vec![];
+    for part in parse_multipart(lines, boundary)? {
+        bytes.extend_from_slice(format!("--{boundary}\r\n").as_bytes());
+        for (name, value) in &part.headers {
+            bytes.extend_from_slice(format!("{name}: {value}\r\n").as_bytes());
+        }
+        bytes.extend_from_slice(b"\r\n");
+        bytes.extend(join_lines(&part.body, b"\r\n", base_dir)?);
+        bytes.extend_from_slice(b"\r\n");
+    }
+    bytes.extend_from_slice(format!("--{boundary}--\r\n").as_bytes());
+    Ok(bytes)
+}
+
+fn join_lines(
+    lines: &[String],
+    separator: &[u8],
+    base_dir: Option<&Path>,
+) -> crate::Result<Vec<u8>> {
+    let lines = lines
+        .iter()
+        .map(|line| match include_path(line) {
+            Some((path, _)) => {
+                let path = include_file(base_dir, path);
+                fs::read(&path).with_context(|| format!("read file {} failed", path.display()))
+            }
+            None => Ok(line.as_bytes().to_vec()),
+        })
+        .collect::<crate::Result<Vec<_>>>()?;
+    Ok(lines.join(separator))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MultipartPart, encode, parse_multipart};
+    use std::fs;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn parses_multipart_parts_with_headers() {
+        let body = lines(
+            "--b\nContent-Disposition: form-data; name=\"title\"\n\nhello\nworld\n--b\nContent-Disposition: form-data; name=\"file\"; filename=\"a.json\"\nContent-Type: application/json\n\n< ./a.json\n--b--",
+        );
+        let parts = parse_multipart(&body, "b").unwrap();
+        assert_eq!(
+            parts,
+            vec![
+                MultipartPart {
+                    headers: vec![(
+                        "Content-Disposition".to_string(),
+                        "form-data; name=\"title\"".to_string()
+                    )],
+                    body: lines("hello\nworld"),
+                },
+                MultipartPart {
+                    headers: vec![
+                        (
+                            "Content-Disposition".to_string(),
+                            "form-data; name=\"file\"; filename=\"a.json\"".to_string()
+                        ),
+                        ("Content-Type".to_string(), "application/json".to_string())
+                    ],
+                    body: lines("< ./a.json"),
+                },
+            ]
+        );
+        assert!(parse_multipart(&lines("no parts"), "b").is_err());
+    }
+
+    #[test]
+    fn encodes_file_includes_relative_to_base_dir() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join("a.json"), "{\"a\":1}").unwrap();
+        let plain = encode(&lines("< ./a.json"), None, Some(dir.path())).unwrap();
+        assert_eq!(plain, b"{\"a\":1}");
+        let multipart = encode(
+            &lines("--b\nContent-Disposition: form-data; name=\"file\"\n\n< a.json\n--b--"),
+            Some("multipart/form-data; boundary=\"b\""),
+            Some(dir.path()),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(multipart).unwrap(),
+            "--b\r\nContent-Disposition: form-data; name=\"file\"\r\n\r\n{\"a\":1}\r\n--b--\r\n"
+        );
+        assert!(encode(&lines("< missing.json"), None, Some(dir.path())).is_err());
+    }
+}
@@ -0,0 +1,125 @@
+use anyhow::{Context, anyhow};
+use reqwest::StatusCode;
+use serde::Deserialize;
+use std::env;
+use std::fs;
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Overrides the default location of the config file.
+const CONFIG_ENV: &str = "DEVKIT_HTTP_CONFIG";
+/// Delay before the first retry, doubled for each next one.
+const RETRY_BACKOFF: Duration = Duration::from_millis(500);
+
+/// Options of the client http requests are sent with, from the config file
+/// and the command line.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case", deny_unknown_fields)]
+pub struct HttpClientOptions {
+    /// Timeout of the whole request in seconds
+    pub timeout: Option<f64>,
+    pub proxy: Option<String>,
+    /// PEM file with CA certificates trusted besides the built-in ones
+    pub cacert: Option<PathBuf>,
+    /// Accept invalid certificates and host names
+    pub insecure: bool,
+    /// Retries of requests failing to connect, timing out or responding 429/502/503/504
+    pub retries: u32,
+    /// Read the body of non-2xx responses instead of failing
+    pub allow_error_status: bool,
+}
+
+impl HttpClientOptions {
+    /// `$DEVKIT_HTTP_CONFIG`, or `http.toml` in the `devkit` config directory,
+    /// e.g. `~/.config/devkit/http.toml`; defaults if it does not exist.
+    pub fn load_config() -> crate::Result<Self> {
+        let path = match env::var_os(CONFIG_ENV) {
+            Some(path) => PathBuf::from(path),
+            None => match env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| env::var_os("HOME").map(|it| PathBuf::from(it).join(".config")))
+            {
+                Some(dir) => dir.join("devkit").join("http.toml"),
+                None => return Ok(Self::default()),
+            },
+        };
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = fs::read_to_string(&path)
+            .with_context(|| format!("read file {} failed", path.display()))?;
+        toml::from_str(&content).with_context(|| format!("Invalid http config {}", path.display()))
+    }
+
+    pub(super) fn builder(&self) -> crate::Result<reqwest::ClientBuilder> {
+        let mut builder = reqwest::Client::builder();
+        if let Some(timeout) = self.timeout {
+            let timeout = Duration::try_from_secs_f64(timeout)
+                .map_err(|_| anyhow!("Invalid timeout: {timeout}"))?;
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            builder = builder.proxy(
+                reqwest::Proxy::all(proxy).with_context(|| format!("Invalid proxy: {proxy}"))?,
+            );
+        }
+        if let Some(cacert) = &self.cacert {
+            let pem = fs::read(cacert)
+                .with_context(|| format!("read file {} failed", cacert.display()))?;
+            for cert in reqwest::Certificate::from_pem_bundle(&pem)
+                .with_context(|| format!("Invalid PEM certificates {}", cacert.display()))?
+            {
+                builder = builder.add_root_certificate(cert);
+            }
+        }
+        if self.insecure {
+            builder = builder
+                .danger_accept_invalid_certs(true)
+                .danger_accept_invalid_hostnames(true);
+        }
+        Ok(builder)
+    }
+
+    /// The delay before retry `attempt` (0 based) of a failed request, `None` if
+    /// it should not be retried.
+    pub(super) fn retry_delay(
+        &self,
+        attempt: u32,
+        result: &Result<StatusCode, &reqwest::Error>,
+    ) -> Option<Duration> {
+        let retryable = match result {
+            Ok(status) => matches!(status.as_u16(), 429 | 502 | 503 | 504),
+            Err(err) => err.is_connect() || err.is_timeout(),
+        };
+        (retryable && attempt < self.retries).then(|| RETRY_BACKOFF * 2u32.pow(attempt.min(16)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HttpClientOptions;
+    use reqwest::StatusCode;
+    use std::time::Duration;
+
+    #[test]
+    fn parses_config_and_backs_off() {
+        let options: HttpClientOptions = toml::from_str(
+            "timeout = 2.5\nproxy = \"http://proxy.corp:3128\"\ninsecure = true\nretries = 2",
+        )
+        .unwrap();
+        assert_eq!(options.timeout, Some(2.5));
+        assert!(options.builder().is_ok());
+        let unavailable = Ok(StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(
+            options.retry_delay(0, &unavailable),
+            Some(Duration::from_millis(500))
+        );
+        assert_eq!(
+            options.retry_delay(1, &unavailable),
+            Some(Duration::from_millis(1000))
+        );
+        assert_eq!(options.retry_delay(2, &unavailable), None);
+        assert_eq!(options.retry_delay(0, &Ok(StatusCode::NOT_FOUND)), None);
+        assert!(toml::from_str::<HttpClientOptions>("retry = 1").is_err());
+    }
+}
@@ -0,0 +1,546 @@
+//! curl command lines to and from `.http` requests.
+//!
+//! The options which change the request are understood: `-X`, `-H`, `-d`/`--data-*`,
+//! `--json`, `-F`/`--form-string`, `-u`, `-A`, `-e`, `-b`, `-G`, `-I`, `-T` and `--url`.
+//! Transfer options such as `-s`, `-L`, `-k` or `-o <file>` are skipped.
+use super::body;
+use super::jetbrains_http::{JetBrainsHttp, ParseBuffer};
+use anyhow::anyhow;
+use base64::Engine;
+use itertools::Itertools;
+use std::path::Path;
+
+const MULTIPART_BOUNDARY: &str = "WebAppBoundary";
+
+/// Options taking a value which don't change the request.
+const IGNORED_WITH_VALUE: &[&str] = &[
+    "-o",
+    "--output",
+    "-m",
+    "--max-time",
+    "--connect-timeout",
+    "--retry",
+    "--retry-delay",
+    "--retry-max-time",
+    "--max-redirs",
+    "-w",
+    "--write-out",
+    "-x",
+    "--proxy",
+    "-U",
+    "--proxy-user",
+    "--cacert",
+    "--capath",
+    "-E",
+    "--cert",
+    "--key",
+    "-c",
+    "--cookie-jar",
+    "-D",
+    "--dump-header",
+    "-r",
+    "--range",
+    "--resolve",
+    "--limit-rate",
+    "--interface",
+    "-K",
+    "--config",
+    "--unix-socket",
+];
+
+/// Short options taking a value, e.g. `-XPOST` or `-X POST`.
+const SHORT_WITH_VALUE: &str = "XHdFuAebTomwxUEcDrK";
+
+impl JetBrainsHttp {
+    pub fn from_curl(input: &str) -> crate::Result<Self> {
+        Ok(parse_curl(input)?.into())
+    }
+
+    pub fn to_curl(&self) -> crate::Result<String> {
+        to_curl(self)
+    }
+}
+
+#[derive(Default)]
+struct Curl {
+    method: Option<String>,
+    url: Option<String>,
+    headers: Vec<(String, String)>,
+    data: Vec<String>,
+    json: Vec<String>,
+    form: Vec<Vec<String>>,
+    upload: Option<String>,
+    get: bool,
+    head: bool,
+}
+
+fn parse_curl(input: &str) -> crate::Result<ParseBuffer> {
+    let words = split_words(input)?;
+    let mut words = words.into_iter();
+    if words.next().as_deref() != Some("curl") {
+        return Err(anyhow!("Not a curl command: {input}"));
+    }
+    let mut curl = Curl::default();
+    while let Some(word) = words.next() {
+        let (option, inline_value) = match word.strip_prefix('-') {
+            Some(short) if !short.starts_with('-') && short.len() > 1 => {
+                // combined short options, `-sSL` or `-XPOST`
+                let mut chars = short.char_indices();
+                let mut option = None;
+                for (idx, c) in chars.by_ref() {
+                    if SHORT_WITH_VALUE.contains(c) {
+                        let value = &short[idx + c.len_utf8()..];
+                        option = Some((format!("-{c}"), Some(value.to_string())));
+                        break;
+                    }
+                    apply_flag(&mut curl, &format!("-{c}"));
+                }
+                match option {
+                    Some((option, Some(value))) if value.is_empty() => (option, None),
+                    Some(option) => option,
+                    None => continue,
+                }
+            }
+            Some(_) => (word, None),
+            None => {
+                curl.url = Some(word);
+                continue;
+            }
+        };
+        if IGNORED_WITH_VALUE.contains(&option.as_str()) {
+            if inline_value.is_none() {
+                words.next();
+            }
+            continue;
+        }
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| words.next())
+                .ok_or_else(|| anyhow!("Missing value of curl option {option}"))
+        };
+        match option.as_str() {
+            "-X" | "--request" => curl.method = Some(value()?.to_uppercase()),
+            "--url" => curl.url = Some(value()?),
+            "-H" | "--header" => {
+                let header = value()?;
+                let (name, value) = header
+                    .split_once(':')
+                    .ok_or_else(|| anyhow!("Invalid curl header: {header}"))?;
+                curl.headers
+                    .push((name.trim().to_string(), value.trim().to_string()));
+            }
+            "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                let data = value()?;
+                curl.data.push(match data.strip_prefix('@') {
+                    Some(path) => format!("< {path}"),
+                    None => data,
+                });
+            }
+            "--data-raw" => curl.data.push(value()?),
+            "--data-urlencode" => curl.data.push(url_encode_data(&value()?)),
+            "--json" => curl.json.push(value()?),
+            "-F" | "--form" => curl.form.push(form_part(&value()?, true)?),
+            "--form-string" => curl.form.push(form_part(&value()?, false)?),
+            "-T" | "--upload-file" => curl.upload = Some(value()?),
+            "-u" | "--user" => {
+                let credentials = base64::prelude::BASE64_STANDARD.encode(value()?);
+                curl.headers
+                    .push(("Authorization".to_string(), format!("Basic {credentials}")));
+            }
+            "-A" | "--user-agent" => curl.headers.push(("User-Agent".to_string(), value()?)),
+            "-e" | "--referer" => curl.headers.push(("Referer".to_string(), value()?)),
+            "-b" | "--cookie" => {
+                let cookie = value()?;
+                // without `=` it is a cookie file
+                if cookie.contains('=') {
+                    curl.headers.push(("Cookie".to_string(), cookie));
+                }
+            }
+            flag => apply_flag(&mut curl, flag),
+        }
+    }
+    curl.into_parse_buffer()
+}
+
+fn apply_flag(curl: &mut Curl, flag: &str) {
+    match flag {
+        "-G" | "--get" => curl.get = true,
+        "-I" | "--head" => curl.head = true,
+        _ => {}
+    }
+}
+
+impl Curl {
+    fn into_parse_buffer(mut self) -> crate::Result<ParseBuffer> {
+        let url = self
+            .url
+            .take()
+            .ok_or_else(|| anyhow!("No url in curl command"))?;
+        let mut url = if url.contains("://") || url.starts_with("{{") {
+            url
+        } else {
+            format!("http://{url}")
+        };
+        let mut body = vec![];
+        let implied_method = if self.head {
+            "HEAD"
+        } else if self.get {
+            if !self.data.is_empty() {
+                let separator = if url.contains('?') { '&' } else { '?' };
+                url = format!("{url}{separator}{}", self.data.join("&"));
+            }
+            "GET"
+        } else if !self.form.is_empty() {
+            self.set_header(
+                "Content-Type",
+                &format!("multipart/form-data; boundary={MULTIPART_BOUNDARY}"),
+            );
+            for part in &self.form {
+                body.push(format!("--{MULTIPART_BOUNDARY}"));
+                body.extend(part.iter().cloned());
+            }
+            body.push(format!("--{MULTIPART_BOUNDARY}--"));
+            "POST"
+        } else if !self.json.is_empty() {
+            self.default_header("Content-Type", "application/json");
+            self.default_header("Accept", "application/json");
+            body.push(self.json.concat());
+            "POST"
+        } else if !self.data.is_empty() {
+            if self.data.len() > 1 && self.data.iter().any(|it| it.starts_with("< ")) {
+                return Err(anyhow!("Can not combine several -d options with @file"));
+            }
+            self.default_header("Content-Type", "application/x-www-form-urlencoded");
+            body.push(self.data.join("&"));
+            "POST"
+        } else if let Some(upload) = &self.upload {
+            body.push(format!("< {upload}"));
+            "PUT"
+        } else {
+            "GET"
+        };
+        let method = self.method.as_deref().unwrap_or(implied_method);
+        let body = body.join("\n").lines().map(str::to_string).collect_vec();
+        Ok(ParseBuffer::new(method, &url, self.headers, body))
+    }
+
+    fn default_header(&mut self, name: &str, value: &str) {
+        if !self
+            .headers
+            .iter()
+            .any(|(k, _)| k.eq_ignore_ascii_case(name))
+        {
+            self.headers.push((name.to_string(), value.to_string()));
+        }
+    }
+
+    fn set_header(&mut self, name: &str, value: &str) {
+        self.headers.retain(|(k, _)| !k.eq_ignore_ascii_case(name));
+        self.headers.push((name.to_string(), value.to_string()));
+    }
+}
+
+/// `name=value`, `name=@file;type=...;filename=...` or `name=<file` as a multipart part.
+fn form_part(value: &str, files: bool) -> crate::Result<Vec<String>> {
+    let (name, content) = value
+        .split_once('=')
+        .ok_or_else(|| anyhow!("Invalid curl form: {value}"))?;
+    let mut lines = vec![];
+    match content.strip_prefix('@').or(content.strip_prefix('<')) {
+        Some(file) if files => {
+            let mut params = file.split(';');
+            let path = params.next().unwrap_or_default();
+            let mut filename = content.starts_with('@').then(|| {
+                Path::new(path)
+                    .file_name()
+                    .map(|it| it.to_string_lossy().to_string())
+                    .unwrap_or_default()
+            });
+            let mut content_type = None;
+            for param in params {
+                match param.split_once('=') {
+                    Some(("type", value)) => content_type = Some(value.to_string()),
+                    Some(("filename", value)) => {
+                        filename = Some(value.trim_matches('"').to_string())
+                    }
+                    _ => {}
+                }
+            }
+            lines.push(match filename {
+                Some(filename) => {
+                    format!(
+                        r#"Content-Disposition: form-data; name="{name}"; filename="{filename}""#
+                    )
+                }
+                None => format!(r#"Content-Disposition: form-data; name="{name}""#),
+            });
+            lines.extend(content_type.map(|it| format!("Content-Type: {it}")));
+            lines.push(String::new());
+            lines.push(format!("< {path}"));
+        }
+        _ => {
+            lines.push(format!(r#"Content-Disposition: form-data; name="{name}""#));
+            lines.push(String::new());
+            lines.extend(content.lines().map(str::to_string));
+        }
+    }
+    Ok(lines)
+}
+
+fn url_encode_data(value: &str) -> String {
+    let encode = |it: &str| {
+        percent_encoding::utf8_percent_encode(it, percent_encoding::NON_ALPHANUMERIC).to_string()
+    };
+    match value.split_once('=') {
+        Some((name, value)) => format!("{name}={}", encode(value)),
+        None => encode(value),
+    }
+}
+
+fn to_curl(request: &ParseBuffer) -> crate::Result<String> {
+    let mut args = vec![];
+    let has_body = !request.body().is_empty();
+    if request.method() != "GET" || has_body {
+        args.push(format!("-X {}", request.method()));
+    }
+    args.push(quote(&request.url()));
+    let boundary = request
+        .header("Content-Type")
+        .and_then(body::multipart_boundary);
+    for (name, value) in request.headers() {
+        if boundary.is_some() && name.eq_ignore_ascii_case("Content-Type") {
+            // curl sets it with its own boundary
+            continue;
+        }
+        args.push(format!("-H {}", quote(&format!("{name}: {value}"))));
+    }
+    if let Some(boundary) = boundary {
+        for part in body::parse_multipart(request.body(), boundary)? {
+            args.push(curl_form(&part));
+        }
+    } else if let [line] = request.body()
+        && let Some((path, _)) = body::include_path(line)
+    {
+        args.push(format!("--data-binary {}", quote(&format!("@{path}"))));
+    } else if has_body {
+        args.push(format!("--data-raw {}", quote(&request.body().join("\n"))));
+    }
+    Ok(format!("curl {}", args.join(" \\\n  ")))
+}
+
+fn curl_form(part: &body::MultipartPart) -> String {
+    let header = |name: &str| {
+        part.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    };
+    let disposition = header("Content-Disposition").unwrap_or_default();
+    let param = |key: &str| {
+        disposition
+            .split(';')
+            .filter_map(|it| it.trim().split_once('='))
+            .find(|(k, _)| *k == key)
+            .map(|(_, v)| v.trim_matches('"').to_string())
+    };
+    let name = param("name").unwrap_or_default();
+    if let [line] = part.body.as_slice()
+        && let Some((path, _)) = body::include_path(line)
+    {
+        let mut value = format!("{name}=@{path}");
+        if let Some(filename) = param("filename") {
+            value.push_str(&format!(";filename={filename}"));
+        }
+        if let Some(content_type) = header("Content-Type") {
+            value.push_str(&format!(";type={content_type}"));
+        }
+        format!("-F {}", quote(&value))
+    } else {
+        format!(
+            "--form-string {}",
+            quote(&format!("{name}={}", part.body.join("\n")))
+        )
+    }
+}
+
+/// Single quote for POSIX shells.
+fn quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Split a shell command line into words, understanding quotes, `$'...'`,
+/// backslash escapes and `\` line continuations.
+fn split_words(input: &str) -> crate::Result<Vec<String>> {
+    let mut words = vec![];
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            c if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            '\\' => match chars.next() {
+                Some('\n') => {}
+                Some('\r') if chars.peek() == Some(&'\n') => {
+                    chars.next();
+                }
+                Some(c) => {
+                    word.push(c);
+                    in_word = true;
+                }
+                None => {}
+            },
+            '\'' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow!("Unterminated ' in curl command")),
+                    }
+                }
+            }
+            '"' => {
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => match chars.next() {
+                            Some('\n') => {}
+                            Some(c @ ('"' | '\\' | '$' | '`')) => word.push(c),
+                            Some(c) => {
+                                word.push('\\');
+                                word.push(c);
+                            }
+                            None => return Err(anyhow!("Unterminated \" in curl command")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow!("Unterminated \" in curl command")),
+                    }
+                }
+            }
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                in_word = true;
+                loop {
+                    match chars.next() {
+                        Some('\'') => break,
+                        Some('\\') => match chars.next() {
+                            Some('n') => word.push('\n'),
+                            Some('t') => word.push('\t'),
+                            Some('r') => word.push('\r'),
+                            Some('x') => {
+                                let hex = (0..2)
+                                    .filter_map(|_| chars.next_if(char::is_ascii_hexdigit))
+                                    .collect::<String>();
+                                word.extend(u8::from_str_radix(&hex, 16).ok().map(char::from));
+                            }
+                            Some('u') => {
+                                let hex = (0..4)
+                                    .filter_map(|_| chars.next_if(char::is_ascii_hexdigit))
+                                    .collect::<String>();
+                                word.extend(
+                                    u32::from_str_radix(&hex, 16).ok().and_then(char::from_u32),
+                                );
+                            }
+                            Some(c) => word.push(c),
+                            None => return Err(anyhow!("Unterminated $' in curl command")),
+                        },
+                        Some(c) => word.push(c),
+                        None => return Err(anyhow!("Unterminated $' in curl command")),
+                    }
+                }
+            }
+            c => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_curl, split_words};
+    use crate::command::http_parser::JetBrainsHttp;
+
+    #[test]
+    fn splits_quotes_escapes_and_continuations() {
+        let words = split_words(
+            "curl 'https://a.com/x?q=1' \\\n  -H \"X-A: \\\"b\\\"\" \\\r\n  --data-raw $'{\"n\":\\'1\\'}\\n' ''",
+        )
+        .unwrap();
+        assert_eq!(
+            words,
+            vec![
+                "curl",
+                "https://a.com/x?q=1",
+                "-H",
+                "X-A: \"b\"",
+                "--data-raw",
+                "{\"n\":'1'}\n",
+                ""
+            ]
+        );
+        assert!(split_words("curl 'oops").is_err());
+    }
+
+    #[test]
+    fn parses_curl_options_into_a_request() {
+        let request = parse_curl(
+            "curl -sSL -XPUT https://api.example.com/items/1 -H 'Accept: application/json' \
+             -u user:pass --json '{\"a\":1}' -o out.json",
+        )
+        .unwrap();
+        assert_eq!(
+            request.to_http(),
+            "###\nPUT https://api.example.com/items/1\nAccept: application/json\n\
+             Authorization: Basic dXNlcjpwYXNz\nContent-Type: application/json\n\n{\"a\":1}"
+        );
+        let request =
+            parse_curl("curl example.com/upload -F title=report -F 'file=@./a.png;type=image/png'")
+                .unwrap();
+        assert_eq!(
+            request.to_http(),
+            "###\nPOST http://example.com/upload\n\
+             Content-Type: multipart/form-data; boundary=WebAppBoundary\n\n\
+             --WebAppBoundary\nContent-Disposition: form-data; name=\"title\"\n\nreport\n\
+             --WebAppBoundary\nContent-Disposition: form-data; name=\"file\"; filename=\"a.png\"\n\
+             Content-Type: image/png\n\n< ./a.png\n--WebAppBoundary--"
+        );
+        let request = parse_curl("curl -G https://a.com/s -d q=dk -d page=2").unwrap();
+        assert_eq!(request.method(), "GET");
+        assert_eq!(request.url(), "https://a.com/s?q=dk&page=2");
+    }
+
+    #[test]
+    fn exports_requests_as_curl() {
+        let request = JetBrainsHttp::parse_all(
+            "POST https://a.com/items\nContent-Type: application/json\n\n{\"name\": \"it's\"}\n\n\
+             ### upload\nPOST https://a.com/upload\n\
+             Content-Type: multipart/form-data; boundary=b\n\n\
+             --b\nContent-Disposition: form-data; name=\"file\"; filename=\"a.png\"\n\n< ./a.png\n--b--",
+        )
+        .unwrap();
+        assert_eq!(
+            request[0].to_curl().unwrap(),
+            "curl -X POST \\\n  'https://a.com/items' \\\n  -H 'Content-Type: application/json' \\\n  \
+             --data-raw '{\"name\": \"it'\\''s\"}'"
+        );
+        assert_eq!(
+            request[1].to_curl().unwrap(),
+            "curl -X POST \\\n  'https://a.com/upload' \\\n  -F 'file=@./a.png;filename=a.png'"
+        );
+        let round_trip = JetBrainsHttp::from_curl(&request[0].to_curl().unwrap()).unwrap();
+        assert_eq!(round_trip.body(), request[0].body());
+    }
+}
@@ -0,0 +1,506 @@
+use super::HttpResponse;
+use super::body;
+use crate::command::formatter::{FormattedValue, guess_formatted_value};
+use crate::command::json::{Json, QueryType};
+use anyhow::{Context, anyhow};
+use serde_json::Value;
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// `>> path` saves the response body, a new `name-1.ext` file is created if the file exists
+const SAVE: &str = ">>";
+/// `>>! path` saves the response body, overwriting the file
+const SAVE_OVERWRITE: &str = ">>!";
+/// `> {% ... %}` inline JavaScript handler
+const SCRIPT_START: &str = "{%";
+pub(super) const SCRIPT_END: &str = "%}";
+
+/// A declarative response handler line of a `.http` request, e.g.
+/// `> status == 200`, `> $.id exists`, `> @token = $.token` or `>> ./out.json`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ResponseHandler {
+    Assert {
+        subject: Subject,
+        op: Operator,
+        expected: Value,
+    },
+    /// `> @name = subject`, the value is a variable of the following requests
+    Capture {
+        name: String,
+        subject: Subject,
+    },
+    Save {
+        path: String,
+        overwrite: bool,
+    },
+    /// JavaScript handlers, `> {% ... %}` or `> handler.js`, are not supported
+    Script(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Subject {
+    Status,
+    /// `header Content-Type`
+    Header(String),
+    Body,
+    /// A JSONPath over the body, as queried by `devkit json query`
+    JsonPath(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Operator {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+    Contains,
+    Matches,
+    Exists,
+}
+
+/// What a handler did with a response, failed assertions fail the request.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Outcome {
+    Passed(String),
+    Failed(String),
+    Captured(String, String),
+    Saved(PathBuf),
+    Skipped(String),
+}
+
+impl Outcome {
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Outcome::Failed(_))
+    }
+}
+
+impl Display for Outcome {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Outcome::Passed(it) => write!(f, "ok      {it}"),
+            Outcome::Failed(it) => write!(f, "FAILED  {it}"),
+            Outcome::Captured(name, value) => write!(f, "@{name} = {value}"),
+            Outcome::Saved(path) => write!(f, "saved   {}", path.display()),
+            Outcome::Skipped(it) => write!(f, "skipped {it}"),
+        }
+    }
+}
+
+/// Whether the handler line starts a `> {%` script not closed on the same line.
+pub(super) fn opens_script(line: &str) -> bool {
+    line.strip_prefix('>')
+        .map(str::trim)
+        .is_some_and(|it| it.starts_with(SCRIPT_START) && !it.ends_with(SCRIPT_END))
+}
+
+/// `> {% ... %}`, or `> path.js` alone, assertions on `.js` values are not scripts.
+pub(super) fn is_script(line: &str) -> bool {
+    line.strip_prefix('>').map(str::trim).is_some_and(|it| {
+        it.starts_with(SCRIPT_START)
+            || (it.ends_with(".js") && !it.starts_with('@') && split_operator(it).is_none())
+    })
+}
+
+impl std::str::FromStr for ResponseHandler {
+    type Err = anyhow::Error;
+
+    fn from_str(line: &str) -> Result<Self, Self::Err> {
+        let invalid = || anyhow!("Invalid response handler: {line}");
+        if let Some(path) = line.strip_prefix(SAVE_OVERWRITE) {
+            return Ok(ResponseHandler::Save {
+                path: path.trim().to_string(),
+                overwrite: true,
+            });
+        } else if let Some(path) = line.strip_prefix(SAVE) {
+            return Ok(ResponseHandler::Save {
+                path: path.trim().to_string(),
+                overwrite: false,
+            });
+        } else if is_script(line) {
+            return Ok(ResponseHandler::Script(line.to_string()));
+        }
+        let text = line.strip_prefix('>').ok_or_else(invalid)?.trim();
+        if let Some(capture) = text.strip_prefix('@') {
+            let (name, subject) = capture.split_once('=').ok_or_else(invalid)?;
+            return Ok(ResponseHandler::Capture {
+                name: name.trim().to_string(),
+                subject: subject.trim().parse()?,
+            });
+        }
+        let (subject, op, expected) = split_operator(text).ok_or_else(invalid)?;
+        let expected = match (op, expected.is_empty()) {
+            (Operator::Exists, true) => Value::Null,
+            (Operator::Exists, false) | (_, true) => return Err(invalid()),
+            _ => serde_json::from_str(expected)
+                .unwrap_or_else(|_| Value::String(expected.to_string())),
+        };
+        Ok(ResponseHandler::Assert {
+            subject: subject.parse()?,
+            op,
+            expected,
+        })
+    }
+}
+
+impl std::str::FromStr for Subject {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.split_once(char::is_whitespace) {
+            _ if s == "status" => Ok(Subject::Status),
+            _ if s == "body" => Ok(Subject::Body),
+            _ if s.starts_with('$') => Ok(Subject::JsonPath(s.to_string())),
+            Some(("header", name)) => Ok(Subject::Header(name.trim().to_string())),
+            _ => Err(anyhow!(
+                "Invalid response handler subject '{s}', expected status, header <name>, body or a JSONPath"
+            )),
+        }
+    }
+}
+
+impl Operator {
+    fn parse(word: &str) -> Option<Self> {
+        Some(match word {
+            "==" => Operator::Eq,
+            "!=" => Operator::Ne,
+            "<" => Operator::Lt,
+            "<=" => Operator::Le,
+            ">" => Operator::Gt,
+            ">=" => Operator::Ge,
+            "contains" => Operator::Contains,
+            "matches" => Operator::Matches,
+            "exists" => Operator::Exists,
+            _ => return None,
+        })
+    }
+}
+
+/// Split `subject op expected` at the first operator outside of brackets,
+/// JSONPath filters may hold operators too.
+fn split_operator(text: &str) -> Option<(&str, Operator, &str)> {
+    let mut depth = 0i32;
+    let mut offset = 0;
+    for word in text.split(' ') {
+        let start = offset;
+        offset += word.len() + 1;
+        if depth == 0
+            && start > 0
+            && let Some(op) = Operator::parse(word)
+        {
+            let rest = text.get(offset..).unwrap_or_default();
+            return Some((text[..start].trim(), op, rest.trim()));
+        }
+        for c in word.chars() {
+            match c {
+                '[' | '(' => depth += 1,
+                ']' | ')' => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    None
+}
+
+impl ResponseHandler {
+    /// Apply the handler to the response, captured values are added to `captured`;
+    /// relative save paths are resolved against `base_dir`.
+    pub(super) fn apply(
+        &self,
+        response: &HttpResponse,
+        base_dir: Option<&Path>,
+        captured: &mut Vec<(String, String)>,
+    ) -> crate::Result<Outcome> {
+        match self {
+            ResponseHandler::Assert {
+                subject,
+                op,
+                expected,
+            } => {
+                let actual = subject.value(response)?;
+                let description = match op {
+                    Operator::Exists => format!("{subject} exists"),
+                    _ => format!("{subject} {op} {}", text(expected)),
+                };
+                if op.test(&actual, expected)? {
+                    Ok(Outcome::Passed(description))
+                } else {
+                    Ok(Outcome::Failed(format!(
+                        "{description}, actual: {}",
+                        summary(&actual)
+                    )))
+                }
+            }
+            ResponseHandler::Capture { name, subject } => {
+                let value = subject.value(response)?;
+                if !exists(&value) {
+                    return Err(anyhow!("Nothing to capture in @{name}, {subject} is empty"));
+                }
+                let value = text(&value);
+                captured.push((name.clone(), value.clone()));
+                Ok(Outcome::Captured(name.clone(), value))
+            }
+            ResponseHandler::Save { path, overwrite } => {
+                let mut path = body::include_file(base_dir, path);
+                if !overwrite {
+                    path = free_path(path);
+                }
+                if let Some(parent) = path.parent().filter(|it| !it.as_os_str().is_empty()) {
+                    fs::create_dir_all(parent)
+                        .with_context(|| format!("create dir {} failed", parent.display()))?;
+                }
+                fs::write(&path, &response.body)
+                    .with_context(|| format!("write file {} failed", path.display()))?;
+                Ok(Outcome::Saved(path))
+            }
+            ResponseHandler::Script(script) => Ok(Outcome::Skipped(format!(
+                "JavaScript handlers are not supported: {}",
+                script.lines().next().unwrap_or_default()
+            ))),
+        }
+    }
+}
+
+impl Subject {
+    fn value(&self, response: &HttpResponse) -> crate::Result<Value> {
+        match self {
+            Subject::Status => Ok(Value::from(response.status.as_u16())),
+            Subject::Header(name) => Ok(response
+                .headers
+                .get(name)
+                .map(|it| Value::String(it.to_str().unwrap_or_default().to_string()))
+                .unwrap_or(Value::Null)),
+            Subject::Body => Ok(Value::String(response.body.clone())),
+            Subject::JsonPath(path) => {
+                let value = guess_formatted_value(&response.body);
+                if let FormattedValue::Text(_) = value {
+                    return Err(anyhow!(
+                        "Can't query {path}, the response body is not structured"
+                    ));
+                }
+                Value::try_from(Json::query(&value, Some(path), Some(QueryType::JsonPath))?)
+            }
+        }
+    }
+}
+
+impl Operator {
+    fn test(&self, actual: &Value, expected: &Value) -> crate::Result<bool> {
+        let number = |value: &Value| match value {
+            Value::Number(it) => it.as_f64(),
+            Value::String(it) => it.trim().parse::<f64>().ok(),
+            _ => None,
+        };
+        let compare = |test: fn(f64, f64) -> bool| match (number(actual), number(expected)) {
+            (Some(actual), Some(expected)) => Ok(test(actual, expected)),
+            _ => Err(anyhow!(
+                "{} or {} is not a number",
+                summary(actual),
+                text(expected)
+            )),
+        };
+        match self {
+            Operator::Eq => Ok(equals(actual, expected)),
+            Operator::Ne => Ok(!equals(actual, expected)),
+            Operator::Lt => compare(|a, b| a < b),
+            Operator::Le => compare(|a, b| a <= b),
+            Operator::Gt => compare(|a, b| a > b),
+            Operator::Ge => compare(|a, b| a >= b),
+            Operator::Contains => Ok(match actual {
+                Value::Array(values) => values.iter().any(|it| equals(it, expected)),
+                Value::Object(map) => map.contains_key(&text(expected)),
+                Value::Null => false,
+                actual => text(actual).contains(&text(expected)),
+            }),
+            Operator::Matches => {
+                let pattern = regex::Regex::new(&text(expected))
+                    .with_context(|| format!("Invalid regex: {}", text(expected)))?;
+                Ok(exists(actual) && pattern.is_match(&text(actual)))
+            }
+            Operator::Exists => Ok(exists(actual)),
+        }
+    }
+}
+
+/// A query matching nothing gives an empty array.
+fn exists(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Array(values) => !values.is_empty(),
+        _ => true,
+    }
+}
+
+/// Header values are strings, `header Content-Length == 12` compares them as text.
+fn equals(actual: &Value, expected: &Value) -> bool {
+    actual == expected || (actual.is_string() && text(actual) == text(expected))
+}
+
+/// Strings without quotes, other values as compact JSON.
+fn text(value: &Value) -> String {
+    match value {
+        Value::String(it) => it.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn summary(value: &Value) -> String {
+    let text = text(value);
+    match text.char_indices().nth(200) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text,
+    }
+}
+
+/// The path, or `name-1.ext`, `name-2.ext`, ... next to it if it exists.
+fn free_path(path: PathBuf) -> PathBuf {
+    if !path.exists() {
+        return path;
+    }
+    let stem = path
+        .file_stem()
+        .map(|it| it.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|it| format!(".{}", it.to_string_lossy()))
+        .unwrap_or_default();
+    (1..)
+        .map(|n| path.with_file_name(format!("{stem}-{n}{extension}")))
+        .find(|it| !it.exists())
+        .unwrap_or(path)
+}
+
+impl Display for Subject {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        match self {
+            Subject::Status => write!(f, "status"),
+            Subject::Header(name) => write!(f, "header {name}"),
+            Subject::Body => write!(f, "body"),
+            Subject::JsonPath(path) => write!(f, "{path}"),
+        }
+    }
+}
+
+impl Display for Operator {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Operator::Eq => "==",
+            Operator::Ne => "!=",
+            Operator::Lt => "<",
+            Operator::Le => "<=",
+            Operator::Gt => ">",
+            Operator::Ge => ">=",
+            Operator::Contains => "contains",
+            Operator::Matches => "matches",
+            Operator::Exists => "exists",
+        };
+        write!(f, "{op}")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Operator, Outcome, ResponseHandler, Subject};
+    use crate::command::http_parser::HttpResponse;
+    use reqwest::header::HeaderMap;
+    use reqwest::{StatusCode, Version};
+    use serde_json::json;
+    use std::time::Duration;
+
+    fn response(body: &str) -> HttpResponse {
+        let mut headers = HeaderMap::new();
+        headers.insert("content-length", body.len().into());
+        HttpResponse {
+            url: "http://localhost/".parse().unwrap(),
+            version: Version::HTTP_11,
+            status: StatusCode::OK,
+            headers,
+            redirects: vec![],
+            elapsed: Duration::ZERO,
+            size: body.len(),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn parses_handler_lines() {
+        let parse = |line: &str| line.parse::<ResponseHandler>().unwrap();
+        assert_eq!(
+            parse("> $.items[?(@.id == 1)].name == \"dk\""),
+            ResponseHandler::Assert {
+                subject: Subject::JsonPath("$.items[?(@.id == 1)].name".to_string()),
+                op: Operator::Eq,
+                expected: json!("dk"),
+            }
+        );
+        assert_eq!(
+            parse("> @token = $.data.token"),
+            ResponseHandler::Capture {
+                name: "token".to_string(),
+                subject: Subject::JsonPath("$.data.token".to_string()),
+            }
+        );
+        assert_eq!(
+            parse(">>! out/user.json"),
+            ResponseHandler::Save {
+                path: "out/user.json".to_string(),
+                overwrite: true,
+            }
+        );
+        assert!(matches!(
+            parse("> {% client.test() %}"),
+            ResponseHandler::Script(_)
+        ));
+        assert!(matches!(
+            parse("> scripts/check.js"),
+            ResponseHandler::Script(_)
+        ));
+        assert!(matches!(
+            parse("> $.file == app.js"),
+            ResponseHandler::Assert { .. }
+        ));
+        assert!(matches!(
+            parse("> body contains bundle.js"),
+            ResponseHandler::Assert { .. }
+        ));
+        assert!("> status".parse::<ResponseHandler>().is_err());
+        assert!("> headers == 1".parse::<ResponseHandler>().is_err());
+    }
+
+    #[test]
+    fn applies_assertions_captures_and_saves() {
+        let response = response(r#"{"id": 7, "tags": ["a", "b"], "token": "t0"}"#);
+        let dir = tempfile::tempdir().unwrap();
+        let mut captured = vec![];
+        let mut apply = |line: &str| {
+            line.parse::<ResponseHandler>()
+                .unwrap()
+                .apply(&response, Some(dir.path()), &mut captured)
+                .unwrap()
+        };
+        assert!(!apply("> status == 200").is_failed());
+        assert!(!apply("> status < 400").is_failed());
+        assert!(!apply("> header Content-Length == 44").is_failed());
+        assert!(!apply("> $.tags contains \"b\"").is_failed());
+        assert!(!apply("> $.token matches ^t\\d$").is_failed());
+        assert!(apply("> $.missing exists").is_failed());
+        assert_eq!(
+            apply("> $.id == 8"),
+            Outcome::Failed("$.id == 8, actual: 7".to_string())
+        );
+        assert_eq!(
+            apply("> @token = $.token"),
+            Outcome::Captured("token".to_string(), "t0".to_string())
+        );
+        apply(">> out.json");
+        assert_eq!(
+            apply(">> out.json"),
+            Outcome::Saved(dir.path().join("out-1.json"))
+        );
+        assert_eq!(captured, vec![("token".to_string(), "t0".to_string())]);
+        assert!(dir.path().join("out.json").exists());
+    }
+}
@@ -0,0 +1,285 @@
+use super::jetbrains_http::{JetBrainsHttp, ParseBuffer};
+use anyhow::{Context, anyhow};
+use base64::Engine;
+use serde::Deserialize;
+use serde_json::{Value, json};
+use std::fmt::{self, Display, Formatter};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Request headers of a browser left out of replayed requests: the client
+/// neither decodes compressed bodies nor reuses the browser connection.
+const SKIPPED_HEADERS: [&str; 8] = [
+    "accept-encoding",
+    "connection",
+    "content-length",
+    "host",
+    "keep-alive",
+    "proxy-connection",
+    "te",
+    "upgrade",
+];
+
+/// An HTTP Archive, e.g. exported by the browser DevTools.
+#[derive(Debug, Clone)]
+pub struct Har {
+    path: PathBuf,
+    pub entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HarFile {
+    log: HarLog,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct HarLog {
+    #[serde(default)]
+    entries: Vec<HarEntry>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarEntry {
+    #[serde(default)]
+    pub started_date_time: String,
+    /// Elapsed time of the request in milliseconds
+    #[serde(default)]
+    pub time: f64,
+    pub request: HarRequest,
+    pub response: HarResponse,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarRequest {
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    pub post_data: Option<HarPostData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarPostData {
+    #[serde(default)]
+    pub mime_type: String,
+    pub text: Option<String>,
+    #[serde(default)]
+    pub params: Vec<HarHeader>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarResponse {
+    /// 0 for requests that got no response, e.g. blocked or cancelled
+    #[serde(default)]
+    pub status: u16,
+    #[serde(default)]
+    pub status_text: String,
+    #[serde(default)]
+    pub headers: Vec<HarHeader>,
+    #[serde(default)]
+    pub content: HarContent,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct HarContent {
+    #[serde(default)]
+    pub mime_type: String,
+    pub text: Option<String>,
+    /// `base64` for binary content
+    pub encoding: Option<String>,
+}
+
+/// Also used for the `params` of form post data.
+#[derive(Debug, Clone, Deserialize)]
+pub struct HarHeader {
+    pub name: String,
+    #[serde(default)]
+    pub value: String,
+}
+
+impl Har {
+    pub fn load(path: &Path) -> crate::Result<Self> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("read file {} failed", path.display()))?;
+        let har: HarFile = serde_json::from_str(&content)
+            .with_context(|| format!("Invalid HAR file {}", path.display()))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            entries: har.log.entries,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Whether the path looks like a HAR file, by its `.har` extension.
+    pub fn is_har(path: &Path) -> bool {
+        path.extension()
+            .is_some_and(|it| it.eq_ignore_ascii_case("har"))
+    }
+
+    /// The entries as json records, with headers as objects and decoded bodies.
+    pub fn to_value(&self) -> Value {
+        Value::Array(self.entries.iter().map(HarEntry::to_value).collect())
+    }
+}
+
+impl Display for Har {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.path.display())
+    }
+}
+
+impl HarEntry {
+    /// The request as a `.http` request named after its method and path.
+    pub fn to_request(&self) -> JetBrainsHttp {
+        let HarRequest {
+            method,
+            url,
+            headers,
+            post_data,
+        } = &self.request;
+        let mut headers: Vec<_> = headers
+            .iter()
+            // HTTP/2 pseudo headers, and the headers the client sets itself
+            .filter(|it| {
+                !it.name.starts_with(':')
+                    && !SKIPPED_HEADERS
+                        .iter()
+                        .any(|skipped| it.name.eq_ignore_ascii_case(skipped))
+            })
+            .map(|it| (it.name.clone(), it.value.clone()))
+            .collect();
+        if let Some(post_data) = post_data.as_ref().filter(|it| !it.mime_type.is_empty())
+            && !headers
+                .iter()
+                .any(|(k, _)| k.eq_ignore_ascii_case("content-type"))
+        {
+            headers.push(("Content-Type".to_string(), post_data.mime_type.clone()));
+        }
+        let body = post_data
+            .as_ref()
+            .map(HarPostData::body)
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let name = url::Url::parse(url).map_or(url.clone(), |it| it.path().to_string());
+        JetBrainsHttp::from(ParseBuffer::new(method, url, headers, body))
+            .with_name(&format!("{method} {name}"))
+    }
+
+    /// The response body, base64 content decoded.
+    pub fn response_body(&self) -> crate::Result<String> {
+        let content = &self.response.content;
+        let text = content.text.clone().unwrap_or_default();
+        match content.encoding.as_deref() {
+            Some("base64") => {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(text.trim())
+                    .map_err(|err| anyhow!("Invalid base64 response content: {err}"))?;
+                Ok(String::from_utf8_lossy(&bytes).to_string())
+            }
+            _ => Ok(text),
+        }
+    }
+
+    fn to_value(&self) -> Value {
+        let headers = |headers: &[HarHeader]| {
+            Value::Object(
+                headers
+                    .iter()
+                    .map(|it| (it.name.to_lowercase(), Value::String(it.value.clone())))
+                    .collect(),
+            )
+        };
+        json!({
+            "startedDateTime": self.started_date_time,
+            "time": self.time,
+            "method": self.request.method,
+            "url": self.request.url,
+            "status": self.response.status,
+            "request": {
+                "headers": headers(&self.request.headers),
+                "body": self.request.post_data.as_ref().map(HarPostData::body),
+            },
+            "response": {
+                "headers": headers(&self.response.headers),
+                "mimeType": self.response.content.mime_type,
+                "body": self.response_body().ok(),
+            },
+        })
+    }
+}
+
+impl HarPostData {
+    /// The text, or the form params url encoded.
+    fn body(&self) -> String {
+        match &self.text {
+            Some(text) => text.clone(),
+            None => serde_urlencoded::to_string(
+                self.params
+                    .iter()
+                    .map(|it| (&it.name, &it.value))
+                    .collect::<Vec<_>>(),
+            )
+            .unwrap_or_default(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Har;
+    use std::fs;
+
+    const HAR: &str = r#"{"log": {"version": "1.2", "entries": [
+        {"startedDateTime": "2026-01-01T00:00:00Z", "time": 42.5,
+         "request": {"method": "POST", "url": "https://example.com/api/items?x=1",
+                     "headers": [{"name": ":authority", "value": "example.com"},
+                                 {"name": "Content-Type", "value": "application/json"},
+                                 {"name": "Content-Length", "value": "9"},
+                                 {"name": "accept-encoding", "value": "gzip, deflate, br, zstd"},
+                                 {"name": "Connection", "value": "keep-alive"},
+                                 {"name": "Host", "value": "example.com"}],
+                     "postData": {"mimeType": "application/json", "text": "{\"id\": 1}"}},
+         "response": {"status": 201, "statusText": "Created",
+                      "headers": [{"name": "Content-Type", "value": "application/json"}],
+                      "content": {"mimeType": "application/json", "text": "eyJvayI6dHJ1ZX0=", "encoding": "base64"}}},
+        {"request": {"method": "POST", "url": "https://example.com/login",
+                     "postData": {"mimeType": "application/x-www-form-urlencoded",
+                                  "params": [{"name": "user", "value": "d k"}]}},
+         "response": {"status": 0}}
+    ]}}"#;
+
+    #[test]
+    fn loads_entries_as_requests_and_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("session.HAR");
+        fs::write(&path, HAR).unwrap();
+        assert!(Har::is_har(&path));
+        let har = Har::load(&path).unwrap();
+        assert_eq!(har.entries.len(), 2);
+        let request = har.entries[0].to_request();
+        assert_eq!(request.name(), Some("POST /api/items"));
+        assert_eq!(
+            request.headers(),
+            [("Content-Type".to_string(), "application/json".to_string())]
+        );
+        assert_eq!(request.body(), [r#"{"id": 1}"#]);
+        assert_eq!(har.entries[1].to_request().body(), ["user=d+k"]);
+        let value = har.to_value();
+        assert_eq!(value[0]["status"], 201);
+        assert_eq!(value[0]["response"]["body"], r#"{"ok":true}"#);
+        assert_eq!(value[0]["request"]["headers"][":authority"], "example.com");
+        assert_eq!(value[1]["response"]["body"], "");
+        fs::write(&path, "{}").unwrap();
+        assert!(Har::load(&path).is_err());
+    }
+}
@@ -1,13 +1,18 @@
-use anyhow::anyhow;
+use super::body;
+use super::handler::{self, Outcome, ResponseHandler};
+use super::response::HttpResponse;
+use super::variables::{Environment, Variables};
+use anyhow::{Context, anyhow};
 use derive_more::{Deref, Display};
 use itertools::Itertools;
 use lazy_static::lazy_static;
 use reqwest::Method;
-use reqwest::header::{HeaderMap, HeaderName};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
 use std::fmt::{Display, Formatter};
+use std::path::{Path, PathBuf};
 use std::str::FromStr;
 use std::sync::Arc;
-use std::{fmt, mem};
+use std::{fmt, fs, mem};
 
 #[derive(Debug, Clone, Deref, Display)]
 pub struct JetBrainsHttp(Arc<ParseBuffer>);
@@ -25,7 +30,7 @@ lazy_static! {
 
 lazy_static! {
     static ref grammar_pattern_url: regex::Regex = {
-        regex::RegexBuilder::new(r"^([A-Z]+)\s+(http[s]?.*)$")
+        regex::RegexBuilder::new(r"^([A-Z]+)\s+((?:http[s]?|\{\{).*)$")
             .case_insensitive(true)
             .build()
             .unwrap()
@@ -34,7 +39,7 @@ lazy_static! {
 
 lazy_static! {
     static ref grammar_pattern_url_default: regex::Regex = {
-        regex::RegexBuilder::new(r"^(http[s]?.*)$")
+        regex::RegexBuilder::new(r"^(http[s]?.*|\{\{[$\w.-]+\}\}\S*)$")
             .case_insensitive(true)
             .build()
             .unwrap()
@@ -51,9 +56,70 @@ lazy_static! {
         regex::Regex::new(r"^([\w\d-]+)\s*:\s*(.*)$").unwrap();
 }
 
+lazy_static! {
+    /// In-file variable declaration, `@name = value`
+    static ref grammar_pattern_declaration: regex::Regex =
+        regex::Regex::new(r"^@([\w.-]+)\s*=\s*(.*)$").unwrap();
+}
+
 impl JetBrainsHttp {
     fn try_parse(s: &str) -> crate::Result<Self> {
+        let buffer = Self::parse_buffers(s)
+            .into_iter()
+            .next()
+            .ok_or(anyhow!("Not a valid input"))?;
+        Ok(JetBrainsHttp(Arc::new(buffer)))
+    }
+
+    /// All `###` separated requests of a `.http` file, in order.
+    pub fn parse_all(s: &str) -> crate::Result<Vec<Self>> {
+        let requests = Self::parse_buffers(s)
+            .into_iter()
+            .map(|it| JetBrainsHttp(Arc::new(it)))
+            .collect_vec();
+        if requests.is_empty() {
+            Err(anyhow!("No http request found"))
+        } else {
+            Ok(requests)
+        }
+    }
+
+    /// All requests of the `.http` file, `< path` bodies are resolved relative to it.
+    pub fn parse_file(path: &Path) -> crate::Result<Vec<Self>> {
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("read file {} failed", path.display()))?;
+        let base_dir = path
+            .parent()
+            .filter(|it| !it.as_os_str().is_empty())
+            .unwrap_or(Path::new("."));
+        let requests = Self::parse_buffers(&content)
+            .into_iter()
+            .map(|mut it| {
+                it.base_dir = Some(base_dir.to_path_buf());
+                JetBrainsHttp(Arc::new(it))
+            })
+            .collect_vec();
+        if requests.is_empty() {
+            Err(anyhow!("No http request found in {}", path.display()))
+        } else {
+            Ok(requests)
+        }
+    }
+
+    pub fn with_name(&self, name: &str) -> Self {
+        let mut buffer = (*self.0).clone();
+        buffer.name = Some(name.to_string());
+        buffer.into()
+    }
+
+    /// Substitute `{{variables}}` in the url, headers and body.
+    pub fn resolve(&self, env: &Environment) -> crate::Result<Self> {
+        Ok(JetBrainsHttp(Arc::new(self.0.resolve(env)?)))
+    }
+
+    fn parse_buffers(s: &str) -> Vec<ParseBuffer> {
         let mut buffers = vec![];
+        let mut declared = vec![];
         let mut buffer = ParseBuffer::default();
         let mut lines = s.lines().enumerate();
         enum ParseStep {
@@ -62,8 +128,10 @@ impl JetBrainsHttp {
             Url,
             Header,
             Body,
+            Handler,
         }
         let mut current_step = ParseStep::Init;
+        let mut in_script = false;
         'line_loop: loop {
             if let Some((_, line)) = lines.next() {
                 loop {
@@ -74,10 +142,16 @@ impl JetBrainsHttp {
                             } else if grammar_pattern_start.is_match(line) {
                                 if buffer.method.is_some() {
                                     let buffer = mem::take(&mut buffer);
-                                    buffers.push(buffer);
+                                    buffers.push(buffer.finish(&declared));
                                 }
                                 current_step = ParseStep::Start;
                                 continue;
+                            } else if let Some((_, [name, value])) = grammar_pattern_declaration
+                                .captures(line)
+                                .map(|it| it.extract())
+                            {
+                                declared.push((name.to_string(), value.trim().to_string()));
+                                continue 'line_loop;
                             } else if grammar_pattern_url.is_match(line)
                                 || grammar_pattern_url_default.is_match(line)
                             {
@@ -91,12 +165,19 @@ impl JetBrainsHttp {
                             if line.trim().is_empty() {
                                 continue 'line_loop;
                             } else if grammar_pattern_start.is_match(line) {
-                                if buffer.name.is_none() {
-                                    buffer.name = Some(line.replace(COMMENT_SYMBOL, ""));
+                                let name = line.replace(COMMENT_SYMBOL, "");
+                                if buffer.name.is_none() && !name.trim().is_empty() {
+                                    buffer.name = Some(name.trim().to_string());
                                 }
                                 continue 'line_loop;
                             } else if grammar_pattern_comments.is_match(line) {
                                 continue 'line_loop;
+                            } else if let Some((_, [name, value])) = grammar_pattern_declaration
+                                .captures(line)
+                                .map(|it| it.extract())
+                            {
+                                declared.push((name.to_string(), value.trim().to_string()));
+                                continue 'line_loop;
                             } else if grammar_pattern_url.is_match(line)
                                 || grammar_pattern_url_default.is_match(line)
                             {
@@ -113,6 +194,9 @@ impl JetBrainsHttp {
                             } else if grammar_pattern_start.is_match(line) {
                                 current_step = ParseStep::Init;
                                 continue;
+                            } else if line.starts_with('>') {
+                                current_step = ParseStep::Handler;
+                                continue;
                             } else if grammar_pattern_comments.is_match(line) {
                                 continue 'line_loop;
                             } else if grammar_pattern_start.is_match(line) {
@@ -151,6 +235,9 @@ impl JetBrainsHttp {
                             } else if grammar_pattern_start.is_match(line) {
                                 current_step = ParseStep::Init;
                                 continue;
+                            } else if line.starts_with('>') {
+                                current_step = ParseStep::Handler;
+                                continue;
                             } else if grammar_pattern_comments.is_match(line) {
                                 continue 'line_loop;
                             } else if let Some((_, [name, value])) =
@@ -165,31 +252,80 @@ impl JetBrainsHttp {
                         }
                         ParseStep::Body => {
                             if line.trim().is_empty() {
+                                // kept, blank lines separate the headers and content of multipart parts
+                                buffer.body.push(String::new());
                                 continue 'line_loop;
                             } else if grammar_pattern_start.is_match(line) {
                                 current_step = ParseStep::Init;
                                 continue;
+                            } else if line.starts_with('>') {
+                                current_step = ParseStep::Handler;
+                                continue;
                             } else if grammar_pattern_comments.is_match(line) {
                                 continue 'line_loop;
+                            } else if let Some((_, [name, value])) = grammar_pattern_declaration
+                                .captures(line)
+                                .map(|it| it.extract())
+                                .filter(|_| ends_declarations(lines.clone().map(|(_, it)| it)))
+                            {
+                                declared.push((name.to_string(), value.trim().to_string()));
+                                continue 'line_loop;
                             } else {
                                 buffer.body.push(line.to_string());
                                 continue 'line_loop;
                             }
                         }
+                        ParseStep::Handler => {
+                            if in_script {
+                                // the lines of a `> {% ... %}` script are kept as one handler
+                                if let Some(script) = buffer.handlers.last_mut() {
+                                    script.push('\n');
+                                    script.push_str(line);
+                                }
+                                in_script = !line.trim_end().ends_with(handler::SCRIPT_END);
+                                continue 'line_loop;
+                            } else if grammar_pattern_start.is_match(line) {
+                                current_step = ParseStep::Init;
+                                continue;
+                            } else if line.starts_with('>') {
+                                in_script = handler::opens_script(line);
+                                buffer.handlers.push(line.trim().to_string());
+                                continue 'line_loop;
+                            } else if grammar_pattern_comments.is_match(line) {
+                                continue 'line_loop;
+                            } else if let Some((_, [name, value])) = grammar_pattern_declaration
+                                .captures(line)
+                                .map(|it| it.extract())
+                            {
+                                declared.push((name.to_string(), value.trim().to_string()));
+                                continue 'line_loop;
+                            } else {
+                                continue 'line_loop;
+                            }
+                        }
                     }
                 }
             } else {
                 if buffer.method.is_some() {
-                    buffers.push(buffer);
+                    buffers.push(buffer.finish(&declared));
                 }
                 break;
             }
         }
-        let buffer = buffers
-            .into_iter()
-            .next()
-            .ok_or(anyhow!("Not a valid input"))?;
-        Ok(JetBrainsHttp(Arc::new(buffer)))
+        buffers
+    }
+}
+
+/// A declaration in a body ends it only when it and the declarations after it
+/// are followed by a blank line, `###` or the end of the file.
+fn ends_declarations<'a>(mut rest: impl Iterator<Item = &'a str>) -> bool {
+    rest.find(|it| !grammar_pattern_declaration.is_match(it))
+        .is_none_or(|it| it.trim().is_empty() || grammar_pattern_start.is_match(it))
+}
+
+impl From<ParseBuffer> for JetBrainsHttp {
+    fn from(value: ParseBuffer) -> Self {
+        JetBrainsHttp(Arc::new(value))
     }
 }
 
@@ -208,14 +344,197 @@ pub struct ParseBuffer {
     url_parts: Vec<String>,
     headers: Vec<(String, String)>,
     body: Vec<String>,
+    /// `> ...` response handler and `>> path` lines, see [`ResponseHandler`]
+    handlers: Vec<String>,
+    /// `@name = value` declarations of the file in effect for this request
+    variables: Vec<(String, String)>,
+    /// Directory of the `.http` file, `< path` bodies are relative to it
+    base_dir: Option<PathBuf>,
+    /// Body of a captured request, sent byte for byte instead of the body lines
+    payload: Option<Vec<u8>>,
+}
+
+impl ParseBuffer {
+    pub(super) fn new(
+        method: &str,
+        url: &str,
+        headers: Vec<(String, String)>,
+        body: Vec<String>,
+    ) -> Self {
+        Self {
+            method: Some(method.to_uppercase()),
+            url_parts: vec![url.to_string()],
+            headers,
+            body,
+            ..Default::default()
+        }
+    }
+
+    /// A captured request, its body is sent as is, neither variables nor
+    /// includes are resolved in it.
+    pub(super) fn captured(
+        method: &str,
+        url: &str,
+        headers: Vec<(String, String)>,
+        payload: Vec<u8>,
+    ) -> Self {
+        let body = String::from_utf8_lossy(&payload)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        Self {
+            payload: Some(payload),
+            ..Self::new(method, url, headers, body)
+        }
+    }
+
+    /// The `### name` of the request, if any.
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn method(&self) -> &str {
+        self.method.as_deref().unwrap_or("GET")
+    }
+
+    /// The url as written, continuation lines joined.
+    pub fn url(&self) -> String {
+        self.url_parts.iter().map(|it| it.trim()).join("")
+    }
+
+    pub fn headers(&self) -> &[(String, String)] {
+        &self.headers
+    }
+
+    pub fn body(&self) -> &[String] {
+        &self.body
+    }
+
+    pub fn handlers(&self) -> &[String] {
+        &self.handlers
+    }
+
+    /// Apply the response handlers of the resolved request, captured values are
+    /// set in `env` for the following requests.
+    pub fn handle(
+        &self,
+        response: &HttpResponse,
+        env: &mut Environment,
+    ) -> crate::Result<Vec<Outcome>> {
+        let mut captured = vec![];
+        let mut outcomes = vec![];
+        for line in &self.handlers {
+            let handler = line.parse::<ResponseHandler>()?;
+            outcomes.push(handler.apply(response, self.base_dir.as_deref(), &mut captured)?);
+        }
+        for (name, value) in captured {
+            env.capture(&name, &value);
+        }
+        Ok(outcomes)
+    }
+
+    /// The request in `.http` syntax, without resolving variables or includes.
+    pub fn to_http(&self) -> String {
+        let mut lines = vec![
+            format!("### {}", self.name().unwrap_or_default())
+                .trim_end()
+                .to_string(),
+        ];
+        lines.push(format!("{} {}", self.method(), self.url()));
+        lines.extend(self.headers.iter().map(|(k, v)| format!("{k}: {v}")));
+        if !self.body.is_empty() {
+            lines.push(String::new());
+            lines.extend(self.body.iter().cloned());
+        }
+        if !self.handlers.is_empty() {
+            lines.push(String::new());
+            lines.extend(self.handlers.iter().cloned());
+        }
+        lines.join("\n")
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    fn finish(mut self, declared: &[(String, String)]) -> Self {
+        while self.body.last().is_some_and(String::is_empty) {
+            self.body.pop();
+        }
+        let leading = self.body.iter().take_while(|it| it.is_empty()).count();
+        self.body.drain(..leading);
+        self.variables = declared.to_vec();
+        self
+    }
+
+    /// The body lines with `<@ path` includes inlined, as variables of the file are substituted too.
+    fn inline_includes(&self) -> crate::Result<Vec<String>> {
+        let mut lines = vec![];
+        for line in &self.body {
+            match body::include_path(line) {
+                Some((path, true)) => {
+                    let path = body::include_file(self.base_dir.as_deref(), path);
+                    let content = fs::read_to_string(&path)
+                        .with_context(|| format!("read file {} failed", path.display()))?;
+                    lines.extend(content.lines().map(str::to_string));
+                }
+                _ => lines.push(line.clone()),
+            }
+        }
+        Ok(lines)
+    }
+
+    fn resolve(&self, env: &Environment) -> crate::Result<Self> {
+        let variables = Variables {
+            declared: &self.variables,
+            env,
+        };
+        let substitute_all = |lines: &[String]| {
+            lines
+                .iter()
+                .map(|it| variables.substitute(it))
+                .collect::<crate::Result<Vec<_>>>()
+        };
+        Ok(Self {
+            name: self.name.clone(),
+            method: self.method.clone(),
+            url_parts: substitute_all(&self.url_parts)?,
+            headers: self
+                .headers
+                .iter()
+                .map(|(k, v)| Ok((variables.substitute(k)?, variables.substitute(v)?)))
+                .collect::<crate::Result<Vec<_>>>()?,
+            body: match self.payload {
+                Some(_) => self.body.clone(),
+                None => substitute_all(&self.inline_includes()?)?,
+            },
+            handlers: self
+                .handlers
+                .iter()
+                .map(|it| {
+                    if handler::is_script(it) {
+                        Ok(it.clone())
+                    } else {
+                        variables.substitute(it)
+                    }
+                })
+                .collect::<crate::Result<Vec<_>>>()?,
+            variables: self.variables.clone(),
+            base_dir: self.base_dir.clone(),
+            payload: self.payload.clone(),
+        })
+    }
 }
 
 impl TryFrom<&ParseBuffer> for url::Url {
     type Error = anyhow::Error;
 
     fn try_from(value: &ParseBuffer) -> Result<Self, Self::Error> {
-        let url_string = value.url_parts.iter().map(|it| it.trim()).join("");
-        let url = url::Url::from_str(&url_string).map_err(|err| anyhow!("Invalid url: {}", err))?;
+        let url =
+            url::Url::from_str(&value.url()).map_err(|err| anyhow!("Invalid url: {}", err))?;
         Ok(url)
     }
 }
@@ -247,7 +566,11 @@ impl TryFrom<&ParseBuffer> for HeaderMap_ {
     fn try_from(value: &ParseBuffer) -> Result<Self, Self::Error> {
         let mut headers = HeaderMap::new();
         for (k, v) in &value.headers {
-            headers.insert(HeaderName::from_str(k)?, v.parse()?);
+            headers.insert(
+                HeaderName::from_str(k)?,
+                HeaderValue::from_bytes(v.as_bytes())
+                    .with_context(|| format!("Invalid value of header {k}"))?,
+            );
         }
         Ok(HeaderMap_(headers))
     }
@@ -274,7 +597,14 @@ impl TryFrom<&ParseBuffer> for reqwest::Request {
         let url = parse_buffer.try_into()?;
         let mut req = reqwest::Request::new(method, url);
         *(req.headers_mut()) = HeaderMap_::try_from(parse_buffer)?.into();
-        let body = parse_buffer.body.join("\n");
+        let body = match &parse_buffer.payload {
+            Some(payload) => payload.clone(),
+            None => body::encode(
+                &parse_buffer.body,
+                parse_buffer.header("Content-Type"),
+                parse_buffer.base_dir.as_deref(),
+            )?,
+        };
         *req.body_mut() = Some(reqwest::Body::from(body));
         Ok(req)
     }
@@ -299,7 +629,7 @@ impl Display for ParseBuffer {
 
 #[cfg(test)]
 mod tests {
-    use crate::command::http_parser::JetBrainsHttp;
+    use crate::command::http_parser::{Environment, JetBrainsHttp};
     use std::str::FromStr;
 
     #[test]
@@ -334,4 +664,190 @@ user-agent: SohuVideoMobile/10.1.90 (Platform/6)
         let target = JetBrainsHttp::from_str(string).unwrap();
         println!("{}", target);
     }
+
+    #[test]
+    fn parses_every_request_of_a_file() {
+        let string = r#"
+### get user
+GET https://example.com/users/1
+Accept: application/json
+
+###
+# no name
+POST https://example.com/users
+Content-Type: application/json
+
+{"name": "dk"}
+
+### delete user
+DELETE https://example.com/users/1
+"#;
+        let requests = JetBrainsHttp::parse_all(string).unwrap();
+        let summary = requests
+            .iter()
+            .map(|it| (it.name(), it.method(), it.url()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    Some("get user"),
+                    "GET",
+                    "https://example.com/users/1".to_string()
+                ),
+                (None, "POST", "https://example.com/users".to_string()),
+                (
+                    Some("delete user"),
+                    "DELETE",
+                    "https://example.com/users/1".to_string()
+                ),
+            ]
+        );
+        assert_eq!(requests[1].body, vec![r#"{"name": "dk"}"#]);
+        assert!(JetBrainsHttp::parse_all("# nothing here").is_err());
+    }
+
+    #[test]
+    fn resolves_declared_and_environment_variables() {
+        let string = r#"
+@path = /users
+### first
+GET {{host}}{{path}}
+Authorization: Bearer {{token}}
+
+###
+@path = /groups
+POST {{host}}{{path}}
+
+{"id": "{{$uuid}}"}
+@kind = group
+
+### body lines that look like declarations
+POST {{host}}/raw
+
+@kind = body
+text
+"#;
+        let env: Environment = {
+            let dir = tempfile::tempdir().unwrap();
+            std::fs::write(
+                dir.path().join("http-client.env.json"),
+                r#"{"dev": {"host": "https://example.com", "token": "t0"}}"#,
+            )
+            .unwrap();
+            Environment::load(dir.path(), "dev").unwrap()
+        };
+        let requests = JetBrainsHttp::parse_all(string)
+            .unwrap()
+            .into_iter()
+            .map(|it| it.resolve(&env).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(requests[0].url(), "https://example.com/users");
+        assert_eq!(
+            requests[0].headers,
+            vec![("Authorization".to_string(), "Bearer t0".to_string())]
+        );
+        assert_eq!(requests[1].url(), "https://example.com/groups");
+        assert!(!requests[1].body[0].contains("{{"));
+        assert_eq!(requests[1].body.len(), 1);
+        assert_eq!(requests[2].body, vec!["@kind = body", "text"]);
+        assert!(
+            JetBrainsHttp::parse_all(string).unwrap()[0]
+                .resolve(&Environment::default())
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn builds_multipart_and_file_include_bodies() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("upload.http");
+        std::fs::write(dir.path().join("data.json"), r#"{"v": "{{v}}"}"#).unwrap();
+        std::fs::write(
+            &file,
+            r#"
+@v = 1
+### upload
+POST https://example.com/upload
+Content-Type: multipart/form-data; boundary=WebAppBoundary
+
+--WebAppBoundary
+Content-Disposition: form-data; name="title"
+
+report
+--WebAppBoundary
+Content-Disposition: form-data; name="data"; filename="data.json"
+Content-Type: application/json
+
+< ./data.json
+--WebAppBoundary--
+
+### substituted
+POST https://example.com/data
+
+<@ data.json
+"#,
+        )
+        .unwrap();
+        let requests = JetBrainsHttp::parse_file(&file).unwrap();
+        let body = |idx: usize| {
+            let request = requests[idx].resolve(&Environment::default()).unwrap();
+            let request = reqwest::Request::try_from(&**request).unwrap();
+            String::from_utf8(request.body().unwrap().as_bytes().unwrap().to_vec()).unwrap()
+        };
+        assert_eq!(
+            body(0),
+            "--WebAppBoundary\r\nContent-Disposition: form-data; name=\"title\"\r\n\r\nreport\r\n\
+             --WebAppBoundary\r\nContent-Disposition: form-data; name=\"data\"; filename=\"data.json\"\r\n\
+             Content-Type: application/json\r\n\r\n{\"v\": \"{{v}}\"}\r\n--WebAppBoundary--\r\n"
+        );
+        assert_eq!(body(1), r#"{"v": "1"}"#);
+    }
+
+    #[test]
+    fn parses_response_handlers() {
+        let string = r#"
+### login
+POST https://example.com/login
+
+{"user": "dk"}
+
+> status == 200
+> {%
+    client.global.set("token", response.body.token);
+%}
+> @token = $.token
+>> ./out/{{name}}.json
+# declared after the handlers
+@who = me
+
+### me
+GET https://example.com/{{who}}
+> header Content-Type contains json
+"#;
+        let requests = JetBrainsHttp::parse_all(string).unwrap();
+        assert_eq!(requests[0].body, vec![r#"{"user": "dk"}"#]);
+        assert_eq!(
+            requests[0].handlers(),
+            [
+                "> status == 200",
+                "> {%\n    client.global.set(\"token\", response.body.token);\n%}",
+                "> @token = $.token",
+                ">> ./out/{{name}}.json",
+            ]
+        );
+        assert_eq!(
+            requests[1].handlers(),
+            ["> header Content-Type contains json"]
+        );
+        assert_eq!(
+            requests[1].resolve(&Environment::default()).unwrap().url(),
+            "https://example.com/me"
+        );
+        let mut env = Environment::default();
+        env.capture("name", "login");
+        let resolved = requests[0].resolve(&env).unwrap();
+        assert_eq!(resolved.handlers()[3], ">> ./out/login.json");
+        assert!(resolved.to_http().ends_with("\n\n> status == 200\n> {%\n    client.global.set(\"token\", response.body.token);\n%}\n> @token = $.token\n>> ./out/login.json"));
+    }
 }
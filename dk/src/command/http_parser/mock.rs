@@ -0,0 +1,468 @@
+use super::body;
+use super::jetbrains_http::{JetBrainsHttp, ParseBuffer};
+use crate::command::formatter::{FormattedValue, guess_formatted_value};
+use anyhow::{Context, anyhow};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const START_SYMBOL: &str = "###";
+/// Json files named `index.json` answer the path of their directory
+const INDEX_FILE: &str = "index.json";
+
+lazy_static! {
+    /// `GET /users/{id}`, `/health` matches any method
+    static ref grammar_pattern_route: regex::Regex =
+        regex::Regex::new(r"^(?:([A-Za-z]+|\*)\s+)?(/\S*)$").unwrap();
+    /// `HTTP/1.1 201 Created` or `201`
+    static ref grammar_pattern_status: regex::Regex =
+        regex::Regex::new(r"^(?:HTTP/[\d.]+\s+)?(\d{3})(?:\s.*)?$").unwrap();
+    static ref grammar_pattern_header: regex::Regex =
+        regex::Regex::new(r"^([\w\d-]+)\s*:\s*(.*)$").unwrap();
+}
+
+/// Canned responses by method and path, from a `.http`-like routes file or a
+/// directory of json files.
+#[derive(Debug, Clone, Default)]
+pub struct MockRoutes(Vec<Route>);
+
+#[derive(Debug, Clone)]
+struct Route {
+    name: Option<String>,
+    /// `None` matches any method
+    method: Option<String>,
+    /// Path segments, `*` and `{name}` match any segment
+    path: Vec<String>,
+    response: MockResponse,
+}
+
+#[derive(Debug, Clone)]
+struct MockResponse {
+    status: u16,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl MockRoutes {
+    /// Routes of the file, or of the json files in the directory.
+    pub fn load(source: &Path) -> crate::Result<Self> {
+        if source.is_dir() {
+            let mut routes = vec![];
+            Self::load_dir(source, &mut vec![], &mut routes)?;
+            routes.sort_by(|a, b| a.path.cmp(&b.path));
+            Ok(Self(routes))
+        } else {
+            let content = fs::read_to_string(source)
+                .with_context(|| format!("read file {} failed", source.display()))?;
+            let base_dir = source
+                .parent()
+                .filter(|it| !it.as_os_str().is_empty())
+                .unwrap_or(Path::new("."));
+            Self::parse(&content, base_dir)
+        }
+    }
+
+    fn load_dir(
+        dir: &Path,
+        prefix: &mut Vec<String>,
+        routes: &mut Vec<Route>,
+    ) -> crate::Result<()> {
+        let entries = fs::read_dir(dir)
+            .with_context(|| format!("read dir {} failed", dir.display()))?
+            .collect::<Result<Vec<_>, _>>()?;
+        for entry in entries {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if path.is_dir() {
+                prefix.push(name);
+                Self::load_dir(&path, prefix, routes)?;
+                prefix.pop();
+                continue;
+            }
+            let Some(stem) = name.strip_suffix(".json") else {
+                continue;
+            };
+            let content = fs::read_to_string(&path)
+                .with_context(|| format!("read file {} failed", path.display()))?;
+            let value = serde_json::from_str(&content)
+                .with_context(|| format!("Invalid json file {}", path.display()))?;
+            let mut segments = prefix.clone();
+            if name != INDEX_FILE {
+                segments.push(stem.to_string());
+            }
+            routes.push(Route {
+                name: Some(path.display().to_string()),
+                method: None,
+                path: segments,
+                response: MockResponse {
+                    status: 200,
+                    headers: vec![],
+                    body: FormattedValue::Json(value).to_string_pretty()?.into_bytes(),
+                }
+                .with_content_type(),
+            });
+        }
+        Ok(())
+    }
+
+    /// `### name` separated routes, a `METHOD /path` line and the response:
+    /// an optional `HTTP/1.1 200 OK` status line with headers, then the body.
+    fn parse(content: &str, base_dir: &Path) -> crate::Result<Self> {
+        let mut blocks: Vec<(Option<String>, Vec<&str>)> = vec![(None, vec![])];
+        for line in content.lines() {
+            if let Some(name) = line.strip_prefix(START_SYMBOL) {
+                let name = name.trim_start_matches('#').trim();
+                blocks.push(((!name.is_empty()).then(|| name.to_string()), vec![]));
+            } else if let Some((_, lines)) = blocks.last_mut() {
+                lines.push(line);
+            }
+        }
+        let mut routes = vec![];
+        for (name, lines) in blocks {
+            let mut lines = lines
+                .into_iter()
+                .skip_while(|it| it.trim().is_empty() || it.trim_start().starts_with('#'));
+            let Some(line) = lines.next() else {
+                continue;
+            };
+            let captures = grammar_pattern_route
+                .captures(line.trim())
+                .ok_or_else(|| anyhow!("Invalid mock route, expected `METHOD /path`: {line}"))?;
+            let method = captures.get(1).map(|it| it.as_str());
+            let path = captures.get(2).map_or("/", |it| it.as_str());
+            let response = MockResponse::parse(lines.collect(), base_dir)?;
+            routes.push(Route {
+                name,
+                method: method.filter(|it| *it != "*").map(str::to_uppercase),
+                path: segments(path),
+                response,
+            });
+        }
+        if routes.is_empty() {
+            Err(anyhow!("No mock route found"))
+        } else {
+            Ok(Self(routes))
+        }
+    }
+
+    /// The first route matching the method and the path, without the query.
+    fn find(&self, method: &str, url: &str) -> Option<&Route> {
+        let path = segments(url.split(['?', '#']).next().unwrap_or_default());
+        self.0.iter().find(|route| {
+            route
+                .method
+                .as_ref()
+                .is_none_or(|it| it.eq_ignore_ascii_case(method))
+                && route.path.len() == path.len()
+                && route.path.iter().zip(&path).all(|(pattern, segment)| {
+                    pattern == "*"
+                        || (pattern.starts_with('{') && pattern.ends_with('}'))
+                        || pattern == segment
+                })
+        })
+    }
+
+    /// `METHOD /path` of every route.
+    pub fn describe(&self) -> Vec<String> {
+        self.0
+            .iter()
+            .map(|it| {
+                format!(
+                    "{} /{} -> {}",
+                    it.method.as_deref().unwrap_or("*"),
+                    it.path.join("/"),
+                    it.response.status
+                )
+            })
+            .collect()
+    }
+}
+
+fn segments(path: &str) -> Vec<String> {
+    path.split('/')
+        .filter(|it| !it.is_empty())
+        .map(|it| {
+            percent_encoding::percent_decode_str(it)
+                .decode_utf8_lossy()
+                .to_string()
+        })
+        .collect()
+}
+
+impl MockResponse {
+    fn parse(lines: Vec<&str>, base_dir: &Path) -> crate::Result<Self> {
+        let mut lines = lines
+            .into_iter()
+            .skip_while(|it| it.trim().is_empty())
+            .peekable();
+        let mut status = 200;
+        let mut headers = vec![];
+        if let Some((_, [code])) = lines
+            .peek()
+            .and_then(|it| grammar_pattern_status.captures(it.trim()))
+            .map(|it| it.extract())
+        {
+            status = code.parse()?;
+            lines.next();
+            for line in lines.by_ref() {
+                if line.trim().is_empty() {
+                    break;
+                }
+                let (_, [name, value]) = grammar_pattern_header
+                    .captures(line)
+                    .map(|it| it.extract())
+                    .ok_or_else(|| anyhow!("Invalid mock response header: {line}"))?;
+                headers.push((name.to_string(), value.trim().to_string()));
+            }
+        }
+        let mut body = lines.collect_vec();
+        while body.last().is_some_and(|it| it.trim().is_empty()) {
+            body.pop();
+        }
+        let body = match body.as_slice() {
+            [line] if body::include_path(line).is_some() => {
+                let (path, _) = body::include_path(line).unwrap_or_default();
+                let path = body::include_file(Some(base_dir), path);
+                fs::read(&path).with_context(|| format!("read file {} failed", path.display()))?
+            }
+            lines => lines.join("\n").into_bytes(),
+        };
+        let response = Self {
+            status,
+            headers,
+            body,
+        };
+        if response.header("Content-Type").is_some() {
+            Ok(response)
+        } else {
+            Ok(response.with_content_type())
+        }
+    }
+
+    /// Json and xml bodies are pretty printed and typed, others are plain text.
+    fn with_content_type(mut self) -> Self {
+        if self.body.is_empty() {
+            return self;
+        }
+        let text = String::from_utf8_lossy(&self.body).to_string();
+        let (content_type, body) = match guess_formatted_value(&text) {
+            value @ FormattedValue::Json(_) => ("application/json", value.to_string_pretty()),
+            value @ FormattedValue::Xml(_) => ("application/xml", value.to_string_pretty()),
+            _ => ("text/plain; charset=utf-8", Ok(text)),
+        };
+        if let Ok(body) = body {
+            self.body = body.into_bytes();
+        }
+        self.headers
+            .push(("Content-Type".to_string(), content_type.to_string()));
+        self
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(k, _)| k.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+}
+
+/// Serve the routes of `source` on `addr` until killed, the routes are reloaded
+/// for every request so edits apply at once. Requests are logged as `.http` text.
+pub fn serve_mock(source: &Path, addr: &str) -> crate::Result<()> {
+    let server =
+        tiny_http::Server::http(addr).map_err(|err| anyhow!("Listen on {addr} failed: {err}"))?;
+    for it in MockRoutes::load(source)?.describe() {
+        println!("{it}");
+    }
+    println!("mock server listening on http://{}\n", server.server_addr());
+    serve(&server, source.to_path_buf())
+}
+
+fn serve(server: &tiny_http::Server, source: PathBuf) -> crate::Result<()> {
+    for mut request in server.incoming_requests() {
+        let method = request.method().as_str().to_string();
+        let url = request.url().to_string();
+        let (name, response) = match MockRoutes::load(&source) {
+            Ok(routes) => match routes.find(&method, &url) {
+                Some(route) => (route.name.clone(), route.response.clone()),
+                None if method == "OPTIONS" => (None, MockResponse::preflight()),
+                None => (None, MockResponse::not_found(&method, &url)),
+            },
+            Err(err) => (None, MockResponse::error(&err)),
+        };
+        println!("{}\n", logged(&mut request, &name, &response).to_http());
+        let mut reply =
+            tiny_http::Response::from_data(response.body).with_status_code(response.status);
+        let cors = [("Access-Control-Allow-Origin".to_string(), "*".to_string())];
+        for (name, value) in response.headers.iter().chain(&cors) {
+            if let Ok(header) = tiny_http::Header::from_bytes(name.as_bytes(), value.as_bytes()) {
+                reply.add_header(header);
+            }
+        }
+        if let Err(err) = request.respond(reply) {
+            eprintln!("respond to {method} {url} failed: {err}");
+        }
+    }
+    Ok(())
+}
+
+/// The incoming request as a `.http` request named after the response status and route.
+fn logged(
+    request: &mut tiny_http::Request,
+    name: &Option<String>,
+    response: &MockResponse,
+) -> JetBrainsHttp {
+    let headers = request
+        .headers()
+        .iter()
+        .map(|it| (it.field.to_string(), it.value.to_string()))
+        .collect_vec();
+    let host = headers
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case("Host"))
+        .map(|(_, v)| v.clone())
+        .unwrap_or_else(|| "localhost".to_string());
+    let mut body = String::new();
+    let _ = request.as_reader().read_to_string(&mut body);
+    let url = format!("http://{host}{}", request.url());
+    let buffer = ParseBuffer::new(
+        request.method().as_str(),
+        &url,
+        headers,
+        body.lines().map(str::to_string).collect(),
+    );
+    let status = match name {
+        Some(name) => format!("{} {name}", response.status),
+        None => response.status.to_string(),
+    };
+    JetBrainsHttp::from(buffer).with_name(&status)
+}
+
+impl MockResponse {
+    fn preflight() -> Self {
+        Self {
+            status: 204,
+            headers: vec![
+                ("Access-Control-Allow-Methods".to_string(), "*".to_string()),
+                ("Access-Control-Allow-Headers".to_string(), "*".to_string()),
+            ],
+            body: vec![],
+        }
+    }
+
+    fn not_found(method: &str, url: &str) -> Self {
+        Self {
+            status: 404,
+            headers: vec![],
+            body: serde_json::json!({ "error": format!("No mock route for {method} {url}") })
+                .to_string()
+                .into_bytes(),
+        }
+        .with_content_type()
+    }
+
+    fn error(err: &anyhow::Error) -> Self {
+        Self {
+            status: 500,
+            headers: vec![],
+            body: serde_json::json!({ "error": format!("{err:#}") })
+                .to_string()
+                .into_bytes(),
+        }
+        .with_content_type()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{MockRoutes, serve};
+    use crate::command::http_parser::{HttpClientOptions, HttpRequest};
+    use std::fs;
+    use std::path::Path;
+    use std::str::FromStr;
+
+    #[test]
+    fn parses_routes_and_matches_paths() {
+        let routes = MockRoutes::parse(
+            r#"
+### get user
+GET /users/{id}
+
+{"id": 1}
+
+### create user
+POST /users
+
+HTTP/1.1 201 Created
+Location: /users/2
+
+### health
+/health
+
+ok
+"#,
+            Path::new("."),
+        )
+        .unwrap();
+        assert_eq!(
+            routes.describe(),
+            vec![
+                "GET /users/{id} -> 200",
+                "POST /users -> 201",
+                "* /health -> 200"
+            ]
+        );
+        let user = routes.find("GET", "/users/7?full=true").unwrap();
+        assert_eq!(user.name.as_deref(), Some("get user"));
+        assert_eq!(
+            user.response.header("Content-Type"),
+            Some("application/json")
+        );
+        assert_eq!(user.response.body, b"{\n  \"id\": 1\n}");
+        assert_eq!(
+            routes
+                .find("post", "/users")
+                .unwrap()
+                .response
+                .header("location"),
+            Some("/users/2")
+        );
+        assert!(routes.find("DELETE", "/health").is_some());
+        assert!(routes.find("GET", "/users").is_none());
+        assert!(MockRoutes::parse("### nothing", Path::new(".")).is_err());
+    }
+
+    #[test]
+    fn serves_a_directory_of_json_files() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::create_dir(dir.path().join("users")).unwrap();
+        fs::write(dir.path().join("users/index.json"), "[1, 2]").unwrap();
+        fs::write(dir.path().join("users/1.json"), r#"{"id":1}"#).unwrap();
+        fs::write(dir.path().join("notes.txt"), "skipped").unwrap();
+        let routes = MockRoutes::load(dir.path()).unwrap();
+        assert_eq!(
+            routes.describe(),
+            vec!["* /users -> 200", "* /users/1 -> 200"]
+        );
+
+        let server = tiny_http::Server::http("127.0.0.1:0").unwrap();
+        let port = server.server_addr().to_ip().unwrap().port();
+        let source = dir.path().to_path_buf();
+        std::thread::spawn(move || serve(&server, source));
+        let get = |path: &str| {
+            let request = HttpRequest::from_str(&format!("http://127.0.0.1:{port}{path}")).unwrap();
+            request.send(&HttpClientOptions::default()).unwrap()
+        };
+        let response = get("/users/1");
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(response.body, "{\n  \"id\": 1\n}");
+        assert_eq!(response.headers["access-control-allow-origin"], "*");
+        let response = get("/missing");
+        assert_eq!(response.status.as_u16(), 404);
+        assert_eq!(
+            response.body,
+            "{\n  \"error\": \"No mock route for GET /missing\"\n}"
+        );
+    }
+}
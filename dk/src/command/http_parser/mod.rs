@@ -1,13 +1,31 @@
+mod body;
+mod client;
+mod curl;
+mod handler;
+mod har;
 mod jetbrains_http;
+mod mock;
+mod raw;
+mod response;
+mod variables;
 use crate::command::formatter::{FormattedValue, parse_formatted_value};
 use anyhow::{Context, anyhow};
+pub use client::HttpClientOptions;
 use derive_more::Display;
+pub use handler::Outcome;
+pub use har::{Har, HarEntry};
 pub use jetbrains_http::*;
 use lazy_static::lazy_static;
-use std::fs;
+pub use mock::serve_mock;
+pub use raw::RawHttpMessage;
+pub use response::{HttpResponse, Redirect};
 use std::path::PathBuf;
 use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+use std::{fs, mem};
 use url::Url;
+pub use variables::Environment;
 
 #[derive(Debug, Clone, Display)]
 pub enum HttpRequest {
@@ -22,7 +40,9 @@ impl TryFrom<&HttpRequest> for Url {
 
     fn try_from(value: &HttpRequest) -> Result<Self, Self::Error> {
         match value {
-            HttpRequest::JetBrainsHttp(it) => Url::try_from(&***it),
+            HttpRequest::JetBrainsHttp(it) => {
+                Url::try_from(&**it.resolve(&Environment::default())?)
+            }
             HttpRequest::Uri(url) => Ok(url.clone()),
             HttpRequest::Filepath(path) => Url::from_file_path(path)
                 .map_err(|_| anyhow!("Invalid file path: {}", path.display())),
@@ -34,7 +54,17 @@ impl FromStr for HttpRequest {
     type Err = anyhow::Error;
 
     fn from_str(value: &str) -> Result<Self, Self::Err> {
-        if let Ok(jetbrains_http) = JetBrainsHttp::from_str(value) {
+        if value.trim_start().starts_with("curl ") {
+            Ok(Self::JetBrainsHttp(JetBrainsHttp::from_curl(value)?))
+        } else if RawHttpMessage::is_request(value) {
+            match RawHttpMessage::parse_all(value.as_bytes())?
+                .into_iter()
+                .next()
+            {
+                Some(RawHttpMessage::Request(request)) => Ok(Self::JetBrainsHttp(request)),
+                _ => Err(anyhow!("Not a valid http request: {value}")),
+            }
+        } else if let Ok(jetbrains_http) = JetBrainsHttp::from_str(value) {
             Ok(Self::JetBrainsHttp(jetbrains_http))
         } else if let Ok(url) = Url::parse(value) {
             let schema = url.scheme().to_lowercase();
@@ -59,60 +89,205 @@ lazy_static! {
     };
 }
 
-impl TryFrom<&HttpRequest> for FormattedValue {
-    type Error = anyhow::Error;
+/// Redirects followed before giving up, the same as reqwest's default policy.
+const MAX_REDIRECTS: usize = 10;
 
-    fn try_from(http_request: &HttpRequest) -> Result<Self, Self::Error> {
-        match http_request.clone() {
-            HttpRequest::JetBrainsHttp(jetbrains_http) => {
-                let text = futures::executor::block_on(async move {
-                    let h = ASYNC_RT.spawn(async move {
-                        let text = reqwest::Client::default()
-                            .execute(reqwest::Request::try_from(&**jetbrains_http)?)
-                            .await
-                            .map_err(|err| {
-                                log::debug!("{}", err);
-                                anyhow!("Invalid http request, {jetbrains_http}")
-                            })?
-                            .text()
-                            .await
-                            .map_err(|err| {
-                                log::debug!("{}", err);
-                                anyhow!("Invalid http response, {jetbrains_http}")
-                            })?;
-                        Ok::<_, anyhow::Error>(text)
-                    });
-                    h.await
-                })??;
-                Ok(parse_formatted_value(&text))
-            }
-            HttpRequest::Uri(url) => {
-                let url = url.clone();
-                let text = futures::executor::block_on(async move {
-                    let h = ASYNC_RT.spawn(async move {
-                        let text = reqwest::get(url.clone())
-                            .await
-                            .map_err(|err| {
-                                log::debug!("{}", err);
-                                anyhow!("Invalid http request, url: {url}")
-                            })?
-                            .text()
-                            .await
-                            .map_err(|err| {
-                                log::debug!("{}", err);
-                                anyhow!("Invalid http response, url: {url}")
-                            })?;
-                        Ok::<_, anyhow::Error>(text)
-                    });
-                    h.await
-                })??;
-                Ok(parse_formatted_value(&text))
-            }
+impl HttpRequest {
+    /// Send the request, or read the file, and return the response body.
+    /// Non-2xx responses are errors unless `allow_error_status` is set.
+    pub fn text(&self, options: &HttpClientOptions) -> crate::Result<String> {
+        match self {
             HttpRequest::Filepath(path) => {
-                let text = fs::read_to_string(&path)
+                let text = fs::read_to_string(path)
                     .with_context(|| format!("read file {} failed", path.display()))?;
-                Ok(parse_formatted_value(&text))
+                Ok(text)
+            }
+            request => {
+                let response = request.send(options)?;
+                if !response.status.is_success() && !options.allow_error_status {
+                    let excerpt = response.body.lines().next().unwrap_or_default();
+                    let excerpt = excerpt.chars().take(200).collect::<String>();
+                    return Err(anyhow!(
+                        "{} responded {}, use --allow-error-status to read the body anyway: {excerpt}",
+                        response.url,
+                        response.status
+                    ));
+                }
+                Ok(response.body)
             }
         }
     }
+
+    /// Send the request and read the whole response.
+    pub fn send(&self, options: &HttpClientOptions) -> crate::Result<HttpResponse> {
+        match self {
+            HttpRequest::JetBrainsHttp(jetbrains_http) => {
+                let jetbrains_http = jetbrains_http.resolve(&Environment::default())?;
+                send(reqwest::Request::try_from(&**jetbrains_http)?, options)
+            }
+            HttpRequest::Uri(url) => send(
+                reqwest::Request::new(reqwest::Method::GET, url.clone()),
+                options,
+            ),
+            HttpRequest::Filepath(path) => Err(anyhow!("Not an http request: {}", path.display())),
+        }
+    }
+
+    /// The response body, or the file, parsed as a formatted value.
+    pub fn to_formatted_value(&self, options: &HttpClientOptions) -> crate::Result<FormattedValue> {
+        Ok(parse_formatted_value(&self.text(options)?))
+    }
+}
+
+/// Send the request on the shared runtime and read the whole response,
+/// following redirects, with the given client options.
+pub fn send(request: reqwest::Request, options: &HttpClientOptions) -> crate::Result<HttpResponse> {
+    let url = request.url().clone();
+    let redirects = Arc::new(Mutex::new(vec![]));
+    let policy = {
+        let redirects = redirects.clone();
+        reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() > MAX_REDIRECTS {
+                return attempt.error("too many redirects");
+            }
+            if let (Some(from), Ok(mut redirects)) = (attempt.previous().last(), redirects.lock()) {
+                redirects.push(Redirect {
+                    status: attempt.status(),
+                    from: from.clone(),
+                    to: attempt.url().clone(),
+                });
+            }
+            attempt.follow()
+        })
+    };
+    let client = options.builder()?.redirect(policy).build()?;
+    let options = options.clone();
+    futures::executor::block_on(async move {
+        let h = ASYNC_RT.spawn(async move {
+            let started = Instant::now();
+            let mut request = request;
+            let mut attempt = 0;
+            let response = loop {
+                // bodies are always in memory, so the request can be cloned
+                let retry = request.try_clone();
+                let result = client.execute(request).await;
+                let delay = match (&result, &retry) {
+                    (_, None) => None,
+                    (Ok(response), _) => options.retry_delay(attempt, &Ok(response.status())),
+                    (Err(err), _) => options.retry_delay(attempt, &Err(err)),
+                };
+                let (Some(delay), Some(retry)) = (delay, retry) else {
+                    break result.with_context(|| format!("Invalid http request, url: {url}"))?;
+                };
+                eprintln!(
+                    "retrying {url} in {} ms ({}/{})",
+                    delay.as_millis(),
+                    attempt + 1,
+                    options.retries
+                );
+                tokio::time::sleep(delay).await;
+                redirects.lock().map_err(|err| anyhow!("{err}"))?.clear();
+                request = retry;
+                attempt += 1;
+            };
+            let final_url = response.url().clone();
+            let version = response.version();
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = response
+                .bytes()
+                .await
+                .with_context(|| format!("Invalid http response, url: {url}"))?;
+            Ok::<_, anyhow::Error>(HttpResponse {
+                url: final_url,
+                version,
+                status,
+                headers,
+                redirects: mem::take(&mut *redirects.lock().map_err(|err| anyhow!("{err}"))?),
+                elapsed: started.elapsed(),
+                size: body.len(),
+                body: String::from_utf8_lossy(&body).to_string(),
+            })
+        });
+        h.await
+    })?
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{HttpClientOptions, HttpRequest, send};
+    use std::io::{Read, Write};
+    use std::net::TcpListener;
+    use std::str::FromStr;
+
+    /// Serve the responses one per connection, `{port}` is replaced by the port.
+    fn serve(responses: &'static [&'static str]) -> u16 {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        std::thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut buf = [0u8; 1024];
+                let _ = stream.read(&mut buf).unwrap();
+                let response = response.replace("{port}", &port.to_string());
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        port
+    }
+
+    #[test]
+    fn records_redirects_and_size() {
+        let port = serve(&[
+            "HTTP/1.1 302 Found\r\nlocation: http://127.0.0.1:{port}/final\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 4\r\nconnection: close\r\n\r\ndone",
+        ]);
+        let request = HttpRequest::from_str(&format!("http://127.0.0.1:{port}/start")).unwrap();
+        let response = request.send(&HttpClientOptions::default()).unwrap();
+        assert_eq!(response.url.path(), "/final");
+        assert_eq!(response.size, 4);
+        assert_eq!(response.redirects.len(), 1);
+        assert_eq!(response.redirects[0].status.as_u16(), 302);
+        assert_eq!(response.redirects[0].from.path(), "/start");
+        assert!(
+            response
+                .to_string()
+                .starts_with(&format!("302 Found http://127.0.0.1:{port}/start -> "))
+        );
+    }
+
+    #[test]
+    fn fails_on_error_status_unless_allowed() {
+        let port = serve(&[
+            "HTTP/1.1 500 Internal Server Error\r\ncontent-length: 5\r\nconnection: close\r\n\r\noops!",
+            "HTTP/1.1 500 Internal Server Error\r\ncontent-length: 5\r\nconnection: close\r\n\r\noops!",
+        ]);
+        let request = HttpRequest::from_str(&format!("http://127.0.0.1:{port}/")).unwrap();
+        let mut options = HttpClientOptions::default();
+        let err = request.text(&options).unwrap_err().to_string();
+        assert_eq!(
+            err,
+            format!(
+                "http://127.0.0.1:{port}/ responded 500 Internal Server Error, use --allow-error-status to read the body anyway: oops!"
+            )
+        );
+        options.allow_error_status = true;
+        assert_eq!(request.text(&options).unwrap(), "oops!");
+    }
+
+    #[test]
+    fn retries_unavailable_responses() {
+        let port = serve(&[
+            "HTTP/1.1 503 Service Unavailable\r\ncontent-length: 0\r\nconnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\ncontent-length: 2\r\nconnection: close\r\n\r\nok",
+        ]);
+        let url = format!("http://127.0.0.1:{port}/").parse().unwrap();
+        let options = HttpClientOptions {
+            retries: 1,
+            ..Default::default()
+        };
+        let response = send(reqwest::Request::new(reqwest::Method::GET, url), &options).unwrap();
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(response.body, "ok");
+    }
 }
@@ -0,0 +1,348 @@
+use super::jetbrains_http::{JetBrainsHttp, ParseBuffer};
+use super::response::HttpResponse;
+use anyhow::{Context, anyhow};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{StatusCode, Version};
+use std::time::Duration;
+use url::Url;
+
+lazy_static! {
+    /// `POST /api?x=1 HTTP/1.1`
+    static ref grammar_pattern_request_line: regex::Regex =
+        regex::Regex::new(r"^([A-Z]+)\s+(\S+)\s+HTTP/(\d(?:\.\d)?)$").unwrap();
+    /// `HTTP/1.1 200 OK`
+    static ref grammar_pattern_status_line: regex::Regex =
+        regex::Regex::new(r"^HTTP/(\d(?:\.\d)?)\s+(\d{3})(?:\s.*)?$").unwrap();
+    /// The start of the next message in a capture holding a request and its response
+    static ref grammar_pattern_start_line: regex::bytes::Regex = regex::bytes::Regex::new(
+        r"(?m)^(?:[A-Z]+ \S+ HTTP/\d(?:\.\d)?\r?$|HTTP/\d(?:\.\d)? \d{3}\b)"
+    )
+    .unwrap();
+}
+
+/// A raw HTTP/1.x message as captured by Wireshark, Burp or `nc`.
+#[derive(Debug, Clone)]
+pub enum RawHttpMessage {
+    Request(JetBrainsHttp),
+    Response(Box<HttpResponse>),
+}
+
+impl RawHttpMessage {
+    /// The messages of a capture, e.g. a request followed by its response.
+    /// Chunked bodies are decoded, others end at `Content-Length` or the next message.
+    pub fn parse_all(input: &[u8]) -> crate::Result<Vec<Self>> {
+        let mut messages = vec![];
+        let mut rest = trim_start(input);
+        let mut last_url = None;
+        while !rest.is_empty() {
+            let (head, tail) = split_head(rest);
+            let head = String::from_utf8_lossy(head);
+            let mut lines = head.lines().map(|it| it.trim_end_matches('\r'));
+            let start_line = lines.next().unwrap_or_default().trim();
+            let headers = parse_headers(lines)?;
+            let header = |name: &str| {
+                headers
+                    .iter()
+                    .find(|(k, _)| k.eq_ignore_ascii_case(name))
+                    .map(|(_, v)| v.as_str())
+            };
+            let (body, consumed) = if header("Transfer-Encoding")
+                .is_some_and(|it| it.to_lowercase().contains("chunked"))
+            {
+                dechunk(tail)?
+            } else if let Some(length) = header("Content-Length").and_then(|it| it.parse().ok()) {
+                let length = tail.len().min(length);
+                (tail[..length].to_vec(), length)
+            } else {
+                let end = grammar_pattern_start_line
+                    .find(tail)
+                    .map_or(tail.len(), |it| it.start());
+                (tail[..end].to_vec(), end)
+            };
+            if let Some((_, [method, target, _])) = grammar_pattern_request_line
+                .captures(start_line)
+                .map(|it| it.extract())
+            {
+                let url = request_url(target, header("Host"))?;
+                last_url = Some(url.clone());
+                // the body is decoded, its length is set again when sent
+                let headers = headers
+                    .into_iter()
+                    .filter(|(k, _)| {
+                        !k.eq_ignore_ascii_case("content-length")
+                            && !k.eq_ignore_ascii_case("transfer-encoding")
+                    })
+                    .collect();
+                messages.push(RawHttpMessage::Request(JetBrainsHttp::from(
+                    ParseBuffer::captured(method, url.as_str(), headers, body),
+                )));
+            } else if let Some((_, [version, status])) = grammar_pattern_status_line
+                .captures(start_line)
+                .map(|it| it.extract())
+            {
+                let mut header_map = HeaderMap::new();
+                for (name, value) in &headers {
+                    // captures may hold UTF-8 or Latin-1 in e.g. `Content-Disposition`
+                    header_map.append(
+                        HeaderName::from_bytes(name.as_bytes()).with_context(|| {
+                            format!("Invalid header name {name} in {start_line}")
+                        })?,
+                        HeaderValue::from_bytes(value.as_bytes()).with_context(|| {
+                            format!("Invalid value of header {name} in {start_line}")
+                        })?,
+                    );
+                }
+                messages.push(RawHttpMessage::Response(Box::new(HttpResponse {
+                    // a response captured alone has no url
+                    url: last_url
+                        .clone()
+                        .unwrap_or_else(|| Url::parse("about:blank").unwrap()),
+                    version: parse_version(version),
+                    status: StatusCode::from_u16(status.parse()?)?,
+                    headers: header_map,
+                    redirects: vec![],
+                    elapsed: Duration::ZERO,
+                    size: body.len(),
+                    body: String::from_utf8_lossy(&body).to_string(),
+                })));
+            } else {
+                return Err(anyhow!("Not an http message start line: {start_line}"));
+            }
+            rest = trim_start(&tail[consumed..]);
+        }
+        if messages.is_empty() {
+            Err(anyhow!("No http message found"))
+        } else {
+            Ok(messages)
+        }
+    }
+
+    /// Whether the text starts like a raw request, `GET /path HTTP/1.1`, rather
+    /// than a `.http` request with `{{variables}}`.
+    pub fn is_request(input: &str) -> bool {
+        input
+            .trim_start()
+            .lines()
+            .next()
+            .and_then(|it| grammar_pattern_request_line.captures(it.trim()))
+            .and_then(|it| it.get(2))
+            .is_some_and(|target| !target.as_str().contains("{{"))
+    }
+}
+
+fn trim_start(input: &[u8]) -> &[u8] {
+    let start = input
+        .iter()
+        .position(|it| !it.is_ascii_whitespace())
+        .unwrap_or(input.len());
+    &input[start..]
+}
+
+/// The head, start line and headers, and everything after the blank line ending it.
+fn split_head(input: &[u8]) -> (&[u8], &[u8]) {
+    let crlf = input
+        .windows(4)
+        .position(|it| it == b"\r\n\r\n")
+        .map(|it| (it, 4));
+    let lf = input
+        .windows(2)
+        .position(|it| it == b"\n\n")
+        .map(|it| (it, 2));
+    match [crlf, lf].into_iter().flatten().min() {
+        Some((end, separator)) => (&input[..end], &input[end + separator..]),
+        None => (input, &[]),
+    }
+}
+
+/// Header lines, folded continuation lines are joined to the previous value.
+fn parse_headers<'a>(lines: impl Iterator<Item = &'a str>) -> crate::Result<Vec<(String, String)>> {
+    let mut headers: Vec<(String, String)> = vec![];
+    for line in lines {
+        if line.starts_with([' ', '\t'])
+            && let Some((_, value)) = headers.last_mut()
+        {
+            value.push(' ');
+            value.push_str(line.trim());
+        } else if let Some((name, value)) = line.split_once(':') {
+            headers.push((name.trim().to_string(), value.trim().to_string()));
+        } else if !line.trim().is_empty() {
+            return Err(anyhow!("Invalid header line: {line}"));
+        }
+    }
+    Ok(headers)
+}
+
+/// Decode a `Transfer-Encoding: chunked` body, returning it and the bytes read.
+/// A capture cut short keeps the chunks read so far.
+fn dechunk(input: &[u8]) -> crate::Result<(Vec<u8>, usize)> {
+    let mut body = vec![];
+    let mut position = 0;
+    let line_end = |from: usize| {
+        input[from..]
+            .iter()
+            .position(|it| *it == b'\n')
+            .map(|it| from + it)
+    };
+    while let Some(end) = line_end(position) {
+        let line = String::from_utf8_lossy(&input[position..end]);
+        let size = line.split(';').next().unwrap_or_default().trim();
+        let size = usize::from_str_radix(size, 16)
+            .map_err(|_| anyhow!("Invalid chunk size: {}", line.trim()))?;
+        position = end + 1;
+        if size == 0 {
+            // trailer headers up to the blank line
+            while let Some(end) = line_end(position) {
+                let blank = input[position..end].iter().all(u8::is_ascii_whitespace);
+                position = end + 1;
+                if blank {
+                    break;
+                }
+            }
+            return Ok((body, position.min(input.len())));
+        }
+        let chunk_end = input.len().min(position + size);
+        body.extend_from_slice(&input[position..chunk_end]);
+        position = chunk_end;
+        for expected in [b'\r', b'\n'] {
+            if input.get(position) == Some(&expected) {
+                position += 1;
+            }
+        }
+    }
+    Ok((body, input.len()))
+}
+
+/// Absolute targets as is, origin-form ones against the `Host` header.
+fn request_url(target: &str, host: Option<&str>) -> crate::Result<Url> {
+    if target.starts_with("http://") || target.starts_with("https://") {
+        return Ok(Url::parse(target)?);
+    }
+    let host = host.ok_or_else(|| anyhow!("No Host header for the request target {target}"))?;
+    let url = if target.starts_with('/') {
+        format!("http://{host}{target}")
+    } else {
+        // authority-form of CONNECT, or `*` of OPTIONS
+        format!("http://{host}/")
+    };
+    Ok(Url::parse(&url)?)
+}
+
+fn parse_version(version: &str) -> Version {
+    match version {
+        "0.9" => Version::HTTP_09,
+        "1.0" => Version::HTTP_10,
+        "2" | "2.0" => Version::HTTP_2,
+        "3" | "3.0" => Version::HTTP_3,
+        _ => Version::HTTP_11,
+    }
+}
+
+impl RawHttpMessage {
+    /// The `Content-Type` and `Content-Encoding` of the message.
+    pub fn content_headers(&self) -> (Option<String>, Option<String>) {
+        let get = |name: &str| match self {
+            RawHttpMessage::Request(request) => request.header(name).map(str::to_string),
+            RawHttpMessage::Response(response) => response
+                .headers
+                .get(name)
+                .and_then(|it| it.to_str().ok())
+                .map(str::to_string),
+        };
+        (get("Content-Type"), get("Content-Encoding"))
+    }
+
+    pub fn body(&self) -> String {
+        match self {
+            RawHttpMessage::Request(request) => request.body().iter().join("\n"),
+            RawHttpMessage::Response(response) => response.body.clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RawHttpMessage;
+    use crate::command::http_parser::Environment;
+
+    #[test]
+    fn parses_a_request_and_its_chunked_response() {
+        let capture = "POST /api/items?q=a%20b&page=2 HTTP/1.1\r\nHost: example.com\r\nContent-Type: application/json\r\nContent-Length: 9\r\n\r\n{\"id\": 1}\
+            HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nTransfer-Encoding: chunked\r\nX-Long: a\r\n  b\r\n\r\n\
+            7\r\n{\"ok\": \r\n5;ext=1\r\ntrue}\r\n0\r\n\r\n";
+        let messages = RawHttpMessage::parse_all(capture.as_bytes()).unwrap();
+        assert_eq!(messages.len(), 2);
+        let RawHttpMessage::Request(request) = &messages[0] else {
+            panic!("not a request")
+        };
+        assert_eq!(request.method(), "POST");
+        assert_eq!(request.url(), "http://example.com/api/items?q=a%20b&page=2");
+        assert_eq!(request.body(), [r#"{"id": 1}"#]);
+        let RawHttpMessage::Response(response) = &messages[1] else {
+            panic!("not a response")
+        };
+        assert_eq!(response.status.as_u16(), 200);
+        assert_eq!(
+            response.url.as_str(),
+            "http://example.com/api/items?q=a%20b&page=2"
+        );
+        assert_eq!(response.headers["x-long"], "a b");
+        assert_eq!(response.body, r#"{"ok": true}"#);
+    }
+
+    #[test]
+    fn sends_captured_bodies_as_is() {
+        let capture = "POST /form HTTP/1.1\r\nHost: example.com\r\nTransfer-Encoding: chunked\r\n\r\n\
+            8\r\na=1\r\nb={\r\n3\r\n{x\n\r\n0\r\n\r\n";
+        let messages = RawHttpMessage::parse_all(capture.as_bytes()).unwrap();
+        let RawHttpMessage::Request(request) = &messages[0] else {
+            panic!("not a request")
+        };
+        // `{{x` is not a variable of a captured body
+        let request = request.resolve(&Environment::default()).unwrap();
+        let request = reqwest::Request::try_from(&**request).unwrap();
+        assert!(request.headers().get("transfer-encoding").is_none());
+        assert_eq!(
+            request.body().unwrap().as_bytes().unwrap(),
+            b"a=1\r\nb={{x\n"
+        );
+    }
+
+    #[test]
+    fn keeps_non_ascii_header_values() {
+        let mut capture = b"HTTP/1.1 200 OK\r\nContent-Disposition: attachment; filename=\"r\xc3\xa9sum\xc3\xa9.pdf\"\r\nSet-Cookie: name=Jos\xe9\r\n\r\n".to_vec();
+        let messages = RawHttpMessage::parse_all(&capture).unwrap();
+        let RawHttpMessage::Response(response) = &messages[0] else {
+            panic!("not a response")
+        };
+        assert_eq!(
+            response.headers["content-disposition"].as_bytes(),
+            "attachment; filename=\"résumé.pdf\"".as_bytes()
+        );
+        assert_eq!(
+            response.headers["set-cookie"].as_bytes(),
+            "name=Jos\u{fffd}".as_bytes()
+        );
+        capture.splice(17..17, b"X-Bad: a\x01b\r\n".iter().copied());
+        let err = RawHttpMessage::parse_all(&capture).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid value of header X-Bad in HTTP/1.1 200 OK"
+        );
+    }
+
+    #[test]
+    fn parses_lf_only_captures_without_length() {
+        let messages =
+            RawHttpMessage::parse_all(b"HTTP/1.0 404 Not Found\nServer: nc\n\nmissing\n").unwrap();
+        let RawHttpMessage::Response(response) = &messages[0] else {
+            panic!("not a response")
+        };
+        assert_eq!(response.status.as_u16(), 404);
+        assert_eq!(response.body, "missing\n");
+        assert!(RawHttpMessage::is_request("GET / HTTP/1.1\nHost: a"));
+        assert!(RawHttpMessage::parse_all(b"GET / HTTP/1.1\n\n").is_err());
+        assert!(RawHttpMessage::parse_all(b"hello").is_err());
+    }
+}
@@ -0,0 +1,56 @@
+use itertools::Itertools;
+use reqwest::header::HeaderMap;
+use reqwest::{StatusCode, Version};
+use std::fmt::{self, Display, Formatter};
+use std::time::Duration;
+use url::Url;
+
+/// A response read in full, with the time from sending the request to the end of the body.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    /// The final url, after redirects
+    pub url: Url,
+    pub version: Version,
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// Redirects followed before the final response, in order
+    pub redirects: Vec<Redirect>,
+    pub elapsed: Duration,
+    /// Size of the body in bytes
+    pub size: usize,
+    pub body: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Redirect {
+    pub status: StatusCode,
+    pub from: Url,
+    pub to: Url,
+}
+
+/// Redirects, the status line with the elapsed time and size, then the headers, e.g.
+/// `HTTP/1.1 200 OK (12 ms, 120 bytes)`.
+impl Display for HttpResponse {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        for Redirect { status, from, to } in &self.redirects {
+            writeln!(f, "{status} {from} -> {to}")?;
+        }
+        write!(
+            f,
+            "{:?} {} ({} ms, {} bytes)",
+            self.version,
+            self.status,
+            self.elapsed.as_millis(),
+            self.size
+        )?;
+        let headers = self
+            .headers
+            .iter()
+            .map(|(k, v)| format!("{}: {}", k, String::from_utf8_lossy(v.as_bytes())))
+            .join("\n");
+        if !headers.is_empty() {
+            write!(f, "\n{headers}")?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,215 @@
+use anyhow::{Context, anyhow};
+use itertools::Itertools;
+use lazy_static::lazy_static;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+
+pub const ENV_FILE: &str = "http-client.env.json";
+pub const PRIVATE_ENV_FILE: &str = "http-client.private.env.json";
+/// Variables of this environment are shared by all the others.
+const SHARED_ENV: &str = "$shared";
+/// In-file variables may refer to each other, stop before a cycle loops forever.
+const MAX_DEPTH: usize = 16;
+
+lazy_static! {
+    static ref grammar_pattern_variable: regex::Regex =
+        regex::Regex::new(r"\{\{\s*([$\w.-]+)\s*\}\}").unwrap();
+}
+
+/// Variables of one environment from the JetBrains environment files.
+#[derive(Debug, Clone, Default)]
+pub struct Environment {
+    variables: BTreeMap<String, String>,
+    /// Captured by `> @name = ...` response handlers, they win over `@name` declarations
+    captured: BTreeMap<String, String>,
+}
+
+impl Environment {
+    /// Load the environment `name` from `http-client.env.json` and
+    /// `http-client.private.env.json` in `dir`, values of the private file win.
+    pub fn load(dir: &Path, name: &str) -> crate::Result<Self> {
+        let files = [ENV_FILE, PRIVATE_ENV_FILE]
+            .into_iter()
+            .map(|it| dir.join(it))
+            .filter(|it| it.exists())
+            .collect_vec();
+        if files.is_empty() {
+            return Err(anyhow!("No {ENV_FILE} found in {}", dir.display()));
+        }
+        let mut variables = BTreeMap::new();
+        let mut names = vec![];
+        let mut found = false;
+        for file in files {
+            let content = fs::read_to_string(&file)
+                .with_context(|| format!("read file {} failed", file.display()))?;
+            let environments: BTreeMap<String, BTreeMap<String, Value>> =
+                serde_json::from_str(&content)
+                    .with_context(|| format!("Invalid environment file {}", file.display()))?;
+            for env in [SHARED_ENV, name] {
+                if let Some(values) = environments.get(env) {
+                    found |= env == name;
+                    variables.extend(values.iter().filter_map(|(key, value)| {
+                        let value = match value {
+                            Value::String(value) => value.clone(),
+                            Value::Number(_) | Value::Bool(_) => value.to_string(),
+                            _ => return None,
+                        };
+                        Some((key.clone(), value))
+                    }));
+                }
+            }
+            names.extend(environments.into_keys().filter(|it| it != SHARED_ENV));
+        }
+        if !found {
+            return Err(anyhow!(
+                "Environment '{name}' not found, available: {}",
+                names.into_iter().unique().join(", ")
+            ));
+        }
+        Ok(Self {
+            variables,
+            captured: BTreeMap::new(),
+        })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.variables.get(name).map(String::as_str)
+    }
+
+    /// Set a variable captured from a response, for the following requests.
+    pub fn capture(&mut self, name: &str, value: &str) {
+        self.captured.insert(name.to_string(), value.to_string());
+    }
+}
+
+/// Resolves `{{name}}` placeholders: dynamic `{{$uuid}}`-like variables, then
+/// captured values, `@name = value` declarations of the file, and the environment.
+pub(crate) struct Variables<'a> {
+    pub declared: &'a [(String, String)],
+    pub env: &'a Environment,
+}
+
+impl Variables<'_> {
+    pub fn substitute(&self, text: &str) -> crate::Result<String> {
+        self.substitute_inner(text, 0)
+    }
+
+    fn substitute_inner(&self, text: &str, depth: usize) -> crate::Result<String> {
+        if depth > MAX_DEPTH {
+            return Err(anyhow!("Variable nesting too deep: {text}"));
+        }
+        let mut result = String::with_capacity(text.len());
+        let mut last = 0;
+        for captures in grammar_pattern_variable.captures_iter(text) {
+            let (Some(placeholder), Some(name)) = (captures.get(0), captures.get(1)) else {
+                continue;
+            };
+            result.push_str(&text[last..placeholder.start()]);
+            result.push_str(&self.value(name.as_str(), depth)?);
+            last = placeholder.end();
+        }
+        result.push_str(&text[last..]);
+        Ok(result)
+    }
+
+    fn value(&self, name: &str, depth: usize) -> crate::Result<String> {
+        if let Some(name) = name.strip_prefix('$') {
+            return dynamic_value(name);
+        }
+        if let Some(value) = self.env.captured.get(name) {
+            Ok(value.clone())
+        } else if let Some((_, value)) = self.declared.iter().rev().find(|(key, _)| key == name) {
+            self.substitute_inner(value, depth + 1)
+        } else if let Some(value) = self.env.get(name) {
+            Ok(value.to_string())
+        } else {
+            Err(anyhow!("Unresolved variable {{{{{name}}}}}"))
+        }
+    }
+}
+
+fn dynamic_value(name: &str) -> crate::Result<String> {
+    match name {
+        "uuid" | "random.uuid" => Ok(uuid::Uuid::new_v4().to_string()),
+        "timestamp" => Ok(chrono::Utc::now().timestamp().to_string()),
+        "isoTimestamp" => Ok(chrono::Utc::now().to_rfc3339()),
+        // the random bits of a v4 uuid, to avoid another dependency
+        "randomInt" => Ok((uuid::Uuid::new_v4().as_u128() % 1001).to_string()),
+        _ => Err(anyhow!("Unknown dynamic variable {{{{${name}}}}}")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ENV_FILE, Environment, PRIVATE_ENV_FILE, Variables};
+    use std::fs;
+
+    #[test]
+    fn loads_environment_with_private_and_shared_values() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(
+            dir.path().join(ENV_FILE),
+            r#"{"$shared": {"version": "v1"}, "dev": {"host": "localhost", "port": 8080}, "prod": {}}"#,
+        )
+        .unwrap();
+        fs::write(
+            dir.path().join(PRIVATE_ENV_FILE),
+            r#"{"dev": {"token": "secret", "host": "127.0.0.1"}}"#,
+        )
+        .unwrap();
+        let env = Environment::load(dir.path(), "dev").unwrap();
+        assert_eq!(env.get("host"), Some("127.0.0.1"));
+        assert_eq!(env.get("port"), Some("8080"));
+        assert_eq!(env.get("token"), Some("secret"));
+        assert_eq!(env.get("version"), Some("v1"));
+        let err = Environment::load(dir.path(), "test").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Environment 'test' not found, available: dev, prod"
+        );
+    }
+
+    #[test]
+    fn substitutes_declared_env_and_dynamic_variables() {
+        let mut env = Environment {
+            variables: [("host".to_string(), "example.com".to_string())]
+                .into_iter()
+                .collect(),
+            ..Default::default()
+        };
+        let declared = [
+            ("base".to_string(), "https://{{host}}/api".to_string()),
+            ("id".to_string(), "1".to_string()),
+            ("id".to_string(), "2".to_string()),
+        ];
+        let variables = Variables {
+            declared: &declared,
+            env: &env,
+        };
+        assert_eq!(
+            variables.substitute("{{base}}/users/{{ id }}").unwrap(),
+            "https://example.com/api/users/2"
+        );
+        let uuid = variables.substitute("{{$uuid}}").unwrap();
+        assert!(uuid::Uuid::parse_str(&uuid).is_ok());
+        assert!(
+            variables
+                .substitute("{{$randomInt}}")
+                .unwrap()
+                .parse::<u32>()
+                .is_ok_and(|it| it <= 1000)
+        );
+        assert_eq!(
+            variables.substitute("{{token}}").unwrap_err().to_string(),
+            "Unresolved variable {{token}}"
+        );
+        env.capture("id", "3");
+        let variables = Variables {
+            declared: &declared,
+            env: &env,
+        };
+        assert_eq!(variables.substitute("{{id}}").unwrap(), "3");
+    }
+}
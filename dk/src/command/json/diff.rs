@@ -0,0 +1,278 @@
+use super::{DiffEntry, DiffPath, DiffPathSegment, Json, QueryType};
+use crate::command::formatter::FormattedValue;
+use itertools::Itertools;
+use serde::{Serialize, Serializer};
+use serde_json::Value;
+use std::collections::BTreeSet;
+use std::fmt::{self, Display, Formatter};
+
+const COLOR_RED: &str = "\x1b[31m";
+const COLOR_GREEN: &str = "\x1b[32m";
+const COLOR_YELLOW: &str = "\x1b[33m";
+const COLOR_RESET: &str = "\x1b[0m";
+
+impl Json {
+    /// Structural diff of two inputs after applying the same query to both sides.
+    pub fn semantic_diff(
+        left: &FormattedValue,
+        right: &FormattedValue,
+        query: Option<&str>,
+        query_type: Option<QueryType>,
+        array_key: Option<&str>,
+    ) -> crate::Result<Vec<DiffEntry>> {
+        let left = Value::try_from(Self::query(left, query, query_type)?)?;
+        let right = Value::try_from(Self::query(right, query, query_type)?)?;
+        Ok(Self::diff_values(&left, &right, array_key))
+    }
+
+    pub fn diff_values(left: &Value, right: &Value, array_key: Option<&str>) -> Vec<DiffEntry> {
+        let mut entries = vec![];
+        diff_recursive(left, right, &DiffPath::default(), array_key, &mut entries);
+        entries
+    }
+
+    pub fn diff_report(entries: &[DiffEntry], color: bool) -> String {
+        if entries.is_empty() {
+            return "no differences".to_string();
+        }
+        let paint = |color_code: &str, line: String| {
+            if color {
+                format!("{color_code}{line}{COLOR_RESET}")
+            } else {
+                line
+            }
+        };
+        let (mut added, mut removed, mut changed) = (0, 0, 0);
+        let mut lines = entries
+            .iter()
+            .map(|entry| match entry {
+                DiffEntry::Added { path, value } => {
+                    added += 1;
+                    paint(COLOR_GREEN, format!("+ {path}: {value}"))
+                }
+                DiffEntry::Removed { path, value } => {
+                    removed += 1;
+                    paint(COLOR_RED, format!("- {path}: {value}"))
+                }
+                DiffEntry::Changed { path, left, right } => {
+                    changed += 1;
+                    paint(COLOR_YELLOW, format!("~ {path}: {left} -> {right}"))
+                }
+            })
+            .collect_vec();
+        lines.push(format!(
+            "{} differences ({added} added, {removed} removed, {changed} changed)",
+            entries.len()
+        ));
+        lines.join("\n")
+    }
+}
+
+fn diff_recursive(
+    left: &Value,
+    right: &Value,
+    path: &DiffPath,
+    array_key: Option<&str>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    match (left, right) {
+        (Value::Object(left), Value::Object(right)) => {
+            let keys = left.keys().chain(right.keys()).collect::<BTreeSet<_>>();
+            for key in keys {
+                let path = path.child(DiffPathSegment::Key(key.to_string()));
+                match (left.get(key), right.get(key)) {
+                    (Some(left), Some(right)) => {
+                        diff_recursive(left, right, &path, array_key, entries)
+                    }
+                    (Some(left), None) => entries.push(DiffEntry::Removed {
+                        path,
+                        value: left.clone(),
+                    }),
+                    (None, Some(right)) => entries.push(DiffEntry::Added {
+                        path,
+                        value: right.clone(),
+                    }),
+                    (None, None) => unreachable!(),
+                }
+            }
+        }
+        (Value::Array(left), Value::Array(right)) => {
+            match array_key.filter(|key| is_keyed(left, key) && is_keyed(right, key)) {
+                Some(key) => diff_keyed_array(left, right, path, key, array_key, entries),
+                None => diff_indexed_array(left, right, path, array_key, entries),
+            }
+        }
+        (left, right) => {
+            if left != right {
+                entries.push(DiffEntry::Changed {
+                    path: path.clone(),
+                    left: left.clone(),
+                    right: right.clone(),
+                })
+            }
+        }
+    }
+}
+
+/// Elements are compared position by position, trailing elements are reported
+/// as added or removed.
+fn diff_indexed_array(
+    left: &[Value],
+    right: &[Value],
+    path: &DiffPath,
+    array_key: Option<&str>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    for (idx, (left, right)) in left.iter().zip(right.iter()).enumerate() {
+        let path = path.child(DiffPathSegment::Index(idx));
+        diff_recursive(left, right, &path, array_key, entries);
+    }
+    for (idx, value) in left.iter().enumerate().skip(right.len()) {
+        entries.push(DiffEntry::Removed {
+            path: path.child(DiffPathSegment::Index(idx)),
+            value: value.clone(),
+        });
+    }
+    for (idx, value) in right.iter().enumerate().skip(left.len()) {
+        entries.push(DiffEntry::Added {
+            path: path.child(DiffPathSegment::Index(idx)),
+            value: value.clone(),
+        });
+    }
+}
+
+fn diff_keyed_array(
+    left: &[Value],
+    right: &[Value],
+    path: &DiffPath,
+    key: &str,
+    array_key: Option<&str>,
+    entries: &mut Vec<DiffEntry>,
+) {
+    let key_of = |value: &Value| value.get(key).cloned().unwrap_or(Value::Null);
+    for left_value in left {
+        let key_value = key_of(left_value);
+        let path = path.child(DiffPathSegment::Matched {
+            key: key.to_string(),
+            value: key_value.clone(),
+        });
+        match right.iter().find(|it| key_of(it) == key_value) {
+            Some(right_value) => diff_recursive(left_value, right_value, &path, array_key, entries),
+            None => entries.push(DiffEntry::Removed {
+                path,
+                value: left_value.clone(),
+            }),
+        }
+    }
+    for right_value in right {
+        let key_value = key_of(right_value);
+        if !left.iter().any(|it| key_of(it) == key_value) {
+            entries.push(DiffEntry::Added {
+                path: path.child(DiffPathSegment::Matched {
+                    key: key.to_string(),
+                    value: key_value,
+                }),
+                value: right_value.clone(),
+            });
+        }
+    }
+}
+
+/// Keyed matching only applies when every element is an object carrying a
+/// unique scalar value for the key.
+fn is_keyed(array: &[Value], key: &str) -> bool {
+    let keys = array
+        .iter()
+        .map(|it| it.get(key).filter(|it| !it.is_object() && !it.is_array()))
+        .collect::<Option<Vec<_>>>();
+    match keys {
+        Some(keys) => keys.iter().map(|it| it.to_string()).all_unique(),
+        None => false,
+    }
+}
+
+impl DiffEntry {
+    pub fn path(&self) -> &DiffPath {
+        match self {
+            DiffEntry::Added { path, .. }
+            | DiffEntry::Removed { path, .. }
+            | DiffEntry::Changed { path, .. } => path,
+        }
+    }
+}
+
+impl DiffPath {
+    pub fn segments(&self) -> &[DiffPathSegment] {
+        &self.0
+    }
+
+    fn child(&self, segment: DiffPathSegment) -> Self {
+        let mut segments = self.0.clone();
+        segments.push(segment);
+        Self(segments)
+    }
+}
+
+impl Display for DiffPath {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        let mut path = "$".to_string();
+        for segment in &self.0 {
+            path = match segment {
+                DiffPathSegment::Key(key) => Json::jsonpath_child(&path, key),
+                DiffPathSegment::Index(idx) => format!("{path}[{idx}]"),
+                DiffPathSegment::Matched { key, value } => {
+                    format!("{path}[?(@.{key} == {value})]")
+                }
+            };
+        }
+        path.fmt(f)
+    }
+}
+
+impl Serialize for DiffPath {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+    use crate::command::json::DiffEntry;
+    use serde_json::json;
+
+    #[test]
+    fn ignores_object_key_order() {
+        let left = json!({"a": 1, "b": {"c": 2, "d": 3}});
+        let right = json!({"b": {"d": 3, "c": 2}, "a": 1});
+        assert!(Json::diff_values(&left, &right, None).is_empty());
+    }
+
+    #[test]
+    fn reports_added_removed_and_changed_entries_by_jsonpath() {
+        let left = json!({"name": "devkit", "tags": ["a", "b"], "old": true});
+        let right = json!({"name": "dk", "tags": ["a", "b", "c"], "new": 1});
+        let paths = Json::diff_values(&left, &right, None)
+            .iter()
+            .map(|it| match it {
+                DiffEntry::Added { path, .. } => format!("+{path}"),
+                DiffEntry::Removed { path, .. } => format!("-{path}"),
+                DiffEntry::Changed { path, .. } => format!("~{path}"),
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(paths, vec!["~$.name", "+$.new", "-$.old", "+$.tags[2]"]);
+    }
+
+    #[test]
+    fn matches_array_elements_by_key_field() {
+        let left = json!([{"id": 1, "v": "a"}, {"id": 2, "v": "b"}]);
+        let right = json!([{"id": 2, "v": "b"}, {"id": 1, "v": "x"}]);
+        let entries = Json::diff_values(&left, &right, Some("id"));
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].path().to_string(), "$[?(@.id == 1)].v");
+        assert_eq!(Json::diff_values(&left, &right, None).len(), 4);
+    }
+}
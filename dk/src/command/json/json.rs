@@ -1,4 +1,5 @@
-use super::{DiffTool, Json, KeyPatternType, QueryType};
+use super::{DiffTool, Json, KeyPatternType, QueryType, xml};
+use anyhow::anyhow;
 use itertools::Itertools;
 use jsonpath_rust::JsonPath;
 use lazy_static::lazy_static;
@@ -70,12 +71,42 @@ impl Json {
                     &value, query, query_type,
                 )?))
             }
-            // FormattedValue::Yaml(value) => {
-            //     let value = serde_json::to_value(value.clone())?;
-            //     let query_result = Self::query_inner(&value, query, query_type)?;
-            //     let value = serde_json::from_value(query_result)?;
-            //     Ok(FormattedValue::Yaml(value))
-            // }
+            FormattedValue::Xml(xml) => {
+                let query = query.map(|it| it.trim()).filter(|it| !it.is_empty());
+                let xpath = match query_type {
+                    Some(QueryType::XPath) => true,
+                    // other queries keep the key pattern search of every input
+                    None => query.is_some_and(|it| it.starts_with(['/', '('])),
+                    Some(_) => false,
+                };
+                match query {
+                    None => Ok(input.clone()),
+                    Some(query) if xpath => Ok(FormattedValue::Json(Self::xpath(xml, query)?)),
+                    query => Ok(FormattedValue::Json(Self::query_inner(
+                        &xml::xml_to_value(xml)?,
+                        query,
+                        query_type,
+                    )?)),
+                }
+            }
+            FormattedValue::Csv(table) => {
+                let value = Value::Array(table.to_records());
+                Ok(FormattedValue::Json(Self::query_inner(
+                    &value, query, query_type,
+                )?))
+            }
+            FormattedValue::Yaml(value) => {
+                let value = serde_json::to_value(value)?;
+                let query_result = Self::query_inner(&value, query, query_type)?;
+                let value = serde_yaml::to_value(query_result)?;
+                Ok(FormattedValue::Yaml(value))
+            }
+            FormattedValue::YamlStream(_) => {
+                let value = Value::try_from(input)?;
+                let query_result = Self::query_inner(&value, query, query_type)?;
+                let value = serde_yaml::to_value(query_result)?;
+                Ok(FormattedValue::Yaml(value))
+            }
             FormattedValue::Toml(value) => {
                 let value = serde_json::to_value(value)?;
                 let query_result = Self::query_inner(&value, query, query_type)?;
@@ -267,6 +298,7 @@ impl Json {
         query_type: Option<QueryType>,
     ) -> crate::Result<(Option<KeyPattern>, Option<QueryType>)> {
         match (query_type, query.is_empty(), query.starts_with("$")) {
+            (Some(QueryType::XPath), _, _) => Err(anyhow!("XPath query requires XML input")),
             (Some(QueryType::JsonPath), _, _) | (None, false, true) => {
                 Ok((None, Some(QueryType::JsonPath)))
             }
@@ -377,6 +409,14 @@ lazy_static! {
     static ref START_NUM_PATTERN: regex::Regex = regex::Regex::new(r"^\d+$").unwrap();
 }
 impl Json {
+    pub(crate) fn jsonpath_child(path: &str, key: &str) -> String {
+        if START_NUM_PATTERN.is_match(key) {
+            format!("{}['{}']", path, key)
+        } else {
+            format!("{}.{}", path, key)
+        }
+    }
+
     fn search_key_recursive(
         jsons: &[&Value],
         key_pattern: &KeyPattern,
@@ -388,11 +428,7 @@ impl Json {
                 Value::Object(map) => {
                     let mut vec = Vec::with_capacity(map.len());
                     for (k, v) in map {
-                        let path = if START_NUM_PATTERN.is_match(k) {
-                            format!("{}['{}']", path, k)
-                        } else {
-                            format!("{}.{}", path, k)
-                        };
+                        let path = Self::jsonpath_child(path, k);
                         let mut children = Self::search_key_recursive(&[v], key_pattern, &path);
                         if key_pattern.match_key(k) {
                             vec.push(JsonpathMatch::from(path.as_str()));
@@ -1,7 +1,11 @@
-use crate::command::formatter::{FormattedValue, parse_formatted_value};
-use crate::command::http_parser::HttpRequest;
+use crate::command::formatter::{FormattedValue, FormattedValueType, parse_formatted_value};
+use crate::command::http_parser::{Har, HttpClientOptions, HttpRequest};
+use anyhow::Context;
 use derive_more::Display;
-use std::fs;
+use serde::Serialize;
+use serde_json::Value;
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, BufWriter, ErrorKind, IsTerminal, Write};
 use std::path::PathBuf;
 use strum::EnumIter;
 
@@ -18,10 +22,16 @@ pub enum JsonCommand {
         query: Option<String>,
         #[arg(
             long,
-            help = "json query type, alias `qt`, jsonpath(jp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
             alias = "qt"
         )]
         query_type: Option<QueryType>,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
         #[arg(long, help = "beauty output", alias = "format", default_value = "true")]
         beauty: bool,
         #[arg(short, long, help = "file to write output")]
@@ -43,30 +53,179 @@ pub enum JsonCommand {
         query: Option<String>,
         #[arg(
             long,
-            help = "json query type, alias `qt`, jsonpath(jp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
             alias = "qt"
         )]
         query_type: Option<QueryType>,
         #[arg(
             long,
-            help = "diff tool to use, alias dt, support idea/zed/vscode, and will auto detect if not set",
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+        #[arg(
+            long,
+            help = "match array elements by this key field instead of by index, alias ak",
+            alias = "ak"
+        )]
+        array_key: Option<String>,
+        #[arg(long, help = "disable colored output")]
+        no_color: bool,
+        #[arg(
+            long,
+            help = "open an external diff tool instead of diffing in terminal, alias dt, support idea/zed/vscode",
             alias = "dt"
         )]
         diff_tool: Option<DiffTool>,
     },
+    #[clap(about = "json patch (RFC 6902) and merge-patch (RFC 7386) tools, alias 'p'", aliases=["p"])]
+    Patch {
+        #[clap(subcommand)]
+        command: PatchCommand,
+    },
+    #[clap(about = "transform json with a jq expression, alias 't/jq'", aliases=["t", "jq"])]
+    Transform {
+        #[arg(
+            short,
+            long,
+            help = "jq expression, eg. 'map(select(.ok)) | group_by(.kind) | map({kind: .[0].kind, n: length})'"
+        )]
+        expr: String,
+        #[arg(
+            help = "json input, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        json: Json,
+        #[arg(
+            short,
+            long,
+            help = "run the expression once on all records of a jsonl/yaml stream instead of on each record"
+        )]
+        slurp: bool,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+        #[arg(short, long, help = "file to write output")]
+        file: Option<PathBuf>,
+    },
+    #[clap(about = "query/filter a jsonl stream record by record with bounded memory, alias 'st'", aliases=["st"])]
+    Stream {
+        #[arg(help = "jsonl file, read from stdin if not set")]
+        input: Option<PathBuf>,
+        #[arg(
+            short,
+            long,
+            help = "extract content from each record using jsonpath/key/value pattern, records without a match are skipped"
+        )]
+        query: Option<String>,
+        #[arg(
+            long,
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            alias = "qt"
+        )]
+        query_type: Option<QueryType>,
+        #[arg(
+            short,
+            long,
+            help = "jq expression run on each record before the query, eg. 'select(.level == \"error\")'"
+        )]
+        expr: Option<String>,
+        #[arg(short, long, help = "file to write output")]
+        file: Option<PathBuf>,
+    },
+    #[clap(about = "json schema (draft 2020-12) inference and validation")]
+    Schema {
+        #[clap(subcommand)]
+        command: SchemaCommand,
+    },
 }
 
-impl super::Command for JsonCommand {
-    fn run(&self) -> crate::Result<()> {
+#[derive(clap::Subcommand)]
+pub enum PatchCommand {
+    #[clap(about = "make a patch which turns left into right, alias 'm'", aliases=["m"])]
+    Make {
+        #[arg(
+            help = "json input, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        left: Json,
+        #[arg(
+            help = "json input, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        right: Json,
+        #[arg(short, long, help = "extract content using jsonpath/key/value pattern")]
+        query: Option<String>,
+        #[arg(
+            long,
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            alias = "qt"
+        )]
+        query_type: Option<QueryType>,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+        #[arg(
+            short,
+            long,
+            help = "make a RFC 7386 merge-patch instead of a RFC 6902 patch"
+        )]
+        merge: bool,
+        #[arg(short, long, help = "file to write output")]
+        file: Option<PathBuf>,
+    },
+    #[clap(about = "apply a patch to a document, alias 'a'", aliases=["a"])]
+    Apply {
+        #[arg(
+            help = "json input, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        doc: Json,
+        #[arg(
+            help = "patch input, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        patch: Json,
+        #[arg(short, long, help = "extract content using jsonpath/key/value pattern")]
+        query: Option<String>,
+        #[arg(
+            long,
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            alias = "qt"
+        )]
+        query_type: Option<QueryType>,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+        #[arg(short, long, help = "apply the patch as a RFC 7386 merge-patch")]
+        merge: bool,
+        #[arg(short, long, help = "file to write output")]
+        file: Option<PathBuf>,
+    },
+}
+
+impl JsonCommand {
+    /// Run the command, inputs that are urls are fetched with `options`.
+    pub fn run(&self, options: &HttpClientOptions) -> crate::Result<()> {
         match self {
             JsonCommand::Beauty {
                 json,
                 query,
                 query_type,
+                input_type,
                 beauty,
                 file,
             } => {
-                let json_value = FormattedValue::try_from(json)?;
+                let json_value = json.to_formatted_value(*input_type, options)?;
                 let content =
                     Json::query_beauty(&json_value, query.as_deref(), *query_type, *beauty)?;
                 if let Some(file) = file {
@@ -82,23 +241,152 @@ impl super::Command for JsonCommand {
                 right,
                 query,
                 query_type,
+                input_type,
+                array_key,
+                no_color,
                 diff_tool,
             } => {
-                let left = FormattedValue::try_from(left)?;
-                let right = FormattedValue::try_from(right)?;
-                Json::diff(
-                    &left,
-                    &right,
+                let left = left.to_formatted_value(*input_type, options)?;
+                let right = right.to_formatted_value(*input_type, options)?;
+                if let Some(diff_tool) = diff_tool {
+                    Json::diff(
+                        &left,
+                        &right,
+                        query.as_deref(),
+                        *query_type,
+                        Some(*diff_tool),
+                    )?;
+                } else {
+                    let entries = Json::semantic_diff(
+                        &left,
+                        &right,
+                        query.as_deref(),
+                        *query_type,
+                        array_key.as_deref(),
+                    )?;
+                    let color = !no_color
+                        && std::io::stdout().is_terminal()
+                        && std::env::var_os("NO_COLOR").is_none();
+                    println!("{}", Json::diff_report(&entries, color));
+                }
+                Ok(())
+            }
+            JsonCommand::Transform {
+                expr,
+                json,
+                slurp,
+                input_type,
+                file,
+            } => {
+                let json_value = json.to_formatted_value(*input_type, options)?;
+                let content = match Json::transform(&json_value, expr, *slurp)? {
+                    FormattedValue::Jsonl(values) => values
+                        .iter()
+                        .map(serde_json::to_string)
+                        .collect::<Result<Vec<_>, _>>()?
+                        .join("\n"),
+                    value => value.to_string_pretty()?,
+                };
+                if let Some(file) = file {
+                    fs::write(file, content)?;
+                    println!("write to {}", file.display());
+                } else {
+                    println!("{content}");
+                }
+                Ok(())
+            }
+            JsonCommand::Stream {
+                input,
+                query,
+                query_type,
+                expr,
+                file,
+            } => {
+                let reader: Box<dyn BufRead> = match input {
+                    Some(path) => {
+                        Box::new(BufReader::new(File::open(path).with_context(|| {
+                            format!("read file {} failed", path.display())
+                        })?))
+                    }
+                    None => Box::new(std::io::stdin().lock()),
+                };
+                let writer: Box<dyn Write> = match file {
+                    Some(file) => Box::new(BufWriter::new(File::create(file)?)),
+                    None => Box::new(BufWriter::new(std::io::stdout().lock())),
+                };
+                match Json::stream(
+                    reader,
+                    writer,
                     query.as_deref(),
                     *query_type,
-                    diff_tool.map(|it| it),
-                )?;
+                    expr.as_deref(),
+                ) {
+                    Ok(stats) => eprintln!("{stats}"),
+                    // the reader of stdout has gone, eg. `| head`
+                    Err(err)
+                        if err
+                            .downcast_ref::<std::io::Error>()
+                            .is_some_and(|it| it.kind() == ErrorKind::BrokenPipe) => {}
+                    Err(err) => return Err(err),
+                }
+                if let Some(file) = file {
+                    println!("write to {}", file.display());
+                }
                 Ok(())
             }
+            JsonCommand::Patch { command } => command.run(options),
+            JsonCommand::Schema { command } => command.run(options),
         }
     }
 }
 
+#[derive(clap::Subcommand)]
+pub enum SchemaCommand {
+    #[clap(about = "infer a schema from one or many samples, jsonl records are merged, alias 'i'", aliases=["i"])]
+    Infer {
+        #[arg(
+            help = "json samples, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        samples: Vec<Json>,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+        #[arg(short, long, help = "file to write output")]
+        file: Option<PathBuf>,
+    },
+    #[clap(about = "validate a document against a schema, alias 'v'", aliases=["v"])]
+    Validate {
+        #[arg(
+            help = "json schema, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        schema: Json,
+        #[arg(
+            help = "json input, jsonl records are validated one by one, support string, file-path, url, cmd",
+            default_value = ""
+        )]
+        doc: Json,
+        #[arg(short, long, help = "extract content using jsonpath/key/value pattern")]
+        query: Option<String>,
+        #[arg(
+            long,
+            help = "json query type, alias `qt`, jsonpath(jp)/xpath(xp)/prefix(p)/suffix(s)/contains(c)/regex(r), and will auto detect if not set",
+            alias = "qt"
+        )]
+        query_type: Option<QueryType>,
+        #[arg(
+            long,
+            help = "force the input type instead of guessing it, alias `it`, json/jsonl/yaml/yamlstream/toml/csv/xml/text",
+            alias = "it"
+        )]
+        input_type: Option<FormattedValueType>,
+    },
+}
+
 #[derive(Debug, Clone, Display)]
 pub enum Json {
     #[display("{_0}")]
@@ -109,6 +397,9 @@ pub enum Json {
     Filepath(PathBuf),
     #[display("{_0}")]
     String(String),
+    /// A `.har` file, its entries are the json records
+    #[display("{_0}")]
+    Har(Har),
 }
 
 /// The input kind recognized by the JSON command before the content is resolved.
@@ -117,6 +408,8 @@ pub enum Json {
 #[derive(Debug, Clone, Copy)]
 pub enum QueryType {
     JsonPath,
+    /// XPath 1.0, for xml input only
+    XPath,
     KeyPattern(KeyPatternType),
 }
 
@@ -152,6 +445,48 @@ pub enum DiffTool {
 
 mod difftool;
 
+/// A single difference between two json values, keyed by the JSONPath of the
+/// entry on the side it was found.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "op", rename_all = "lowercase")]
+pub enum DiffEntry {
+    Added {
+        path: DiffPath,
+        value: Value,
+    },
+    Removed {
+        path: DiffPath,
+        value: Value,
+    },
+    Changed {
+        path: DiffPath,
+        left: Value,
+        right: Value,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct DiffPath(Vec<DiffPathSegment>);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DiffPathSegment {
+    Key(String),
+    Index(usize),
+    /// array element matched by `--array-key`, rendered as a JSONPath filter
+    Matched {
+        key: String,
+        value: Value,
+    },
+}
+
+mod diff;
+mod patch;
+mod schema;
+mod stream;
+mod transform;
+pub(crate) mod xml;
+pub use stream::StreamStats;
+
 #[derive(Debug, Copy, Clone, Display, EnumIter, Default)]
 #[display(rename_all = "lowercase")]
 pub enum JetbrainsIDE {
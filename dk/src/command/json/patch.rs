@@ -0,0 +1,436 @@
+use super::{DiffEntry, DiffPathSegment, Json, PatchCommand, QueryType};
+use crate::command::convert::{Conversion, convert};
+use crate::command::formatter::{FormattedValue, FormattedValueType};
+use crate::command::http_parser::HttpClientOptions;
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde_json::{Map, Value, json};
+use std::fs;
+use std::path::PathBuf;
+
+impl PatchCommand {
+    pub fn run(&self, options: &HttpClientOptions) -> crate::Result<()> {
+        let (content, file) = match self {
+            PatchCommand::Make {
+                left,
+                right,
+                query,
+                query_type,
+                input_type,
+                merge,
+                file,
+            } => {
+                let left =
+                    Json::patch_prepare(left, query.as_deref(), *query_type, *input_type, options)?;
+                let right = Json::patch_prepare(
+                    right,
+                    query.as_deref(),
+                    *query_type,
+                    *input_type,
+                    options,
+                )?;
+                let patch = if *merge {
+                    Json::merge_patch_make(&left, &right)
+                } else {
+                    Json::patch_make(&left, &right)
+                };
+                (serde_json::to_string_pretty(&patch)?, file)
+            }
+            PatchCommand::Apply {
+                doc,
+                patch,
+                query,
+                query_type,
+                input_type,
+                merge,
+                file,
+            } => {
+                let doc = Json::query(
+                    &doc.to_formatted_value(*input_type, options)?,
+                    query.as_deref(),
+                    *query_type,
+                )?;
+                let doc_type = doc.type_();
+                let patch = Value::try_from(patch.to_formatted_value(None, options)?)?;
+                let patched = if *merge {
+                    Json::merge_patch_apply(Value::try_from(doc)?, &patch)
+                } else {
+                    Json::patch_apply(Value::try_from(doc)?, &patch)?
+                };
+                let patched = match doc_type {
+                    FormattedValueType::Text => FormattedValue::Json(patched),
+                    // the document keeps its type, what the type can not hold is reported
+                    doc_type => {
+                        let Conversion { value, lossy } =
+                            convert(FormattedValue::Json(patched), doc_type)?;
+                        for it in &lossy {
+                            eprintln!("lossy: {it}");
+                        }
+                        value
+                    }
+                };
+                (patched.to_string_pretty()?, file)
+            }
+        };
+        write_output(content, file)
+    }
+}
+
+fn write_output(content: String, file: &Option<PathBuf>) -> crate::Result<()> {
+    if let Some(file) = file {
+        fs::write(file, content)?;
+        println!("write to {}", file.display());
+    } else {
+        println!("{content}");
+    }
+    Ok(())
+}
+
+impl Json {
+    fn patch_prepare(
+        input: &Json,
+        query: Option<&str>,
+        query_type: Option<QueryType>,
+        input_type: Option<FormattedValueType>,
+        options: &HttpClientOptions,
+    ) -> crate::Result<Value> {
+        let value = Self::query(
+            &input.to_formatted_value(input_type, options)?,
+            query,
+            query_type,
+        )?;
+        Value::try_from(value)
+    }
+
+    /// Make a RFC 6902 patch document from the structural diff of `left` and `right`.
+    pub fn patch_make(left: &Value, right: &Value) -> Value {
+        let entries = Self::diff_values(left, right, None);
+        // trailing array elements are reported in ascending order, but have to be
+        // removed from the end so that the remaining indexes stay valid
+        let operations = entries
+            .iter()
+            .chunk_by(|entry| match entry {
+                DiffEntry::Removed { path, .. } => match path.segments().split_last() {
+                    Some((DiffPathSegment::Index(_), parent)) => Some(json_pointer(parent)),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .into_iter()
+            .flat_map(|(removed_from_array, chunk)| {
+                let chunk = chunk.collect_vec();
+                if removed_from_array.is_some() {
+                    chunk.into_iter().rev().collect_vec()
+                } else {
+                    chunk
+                }
+            })
+            .map(|entry| match entry {
+                DiffEntry::Added { path, value } => {
+                    json!({"op": "add", "path": json_pointer(path.segments()), "value": value})
+                }
+                DiffEntry::Removed { path, .. } => {
+                    json!({"op": "remove", "path": json_pointer(path.segments())})
+                }
+                DiffEntry::Changed { path, right, .. } => {
+                    json!({"op": "replace", "path": json_pointer(path.segments()), "value": right})
+                }
+            })
+            .collect_vec();
+        Value::Array(operations)
+    }
+
+    /// Apply a RFC 6902 patch document, all operations succeed or none is applied.
+    pub fn patch_apply(mut doc: Value, patch: &Value) -> crate::Result<Value> {
+        let operations = patch
+            .as_array()
+            .ok_or_else(|| anyhow!("Invalid json patch, expect an array of operations"))?;
+        for (idx, operation) in operations.iter().enumerate() {
+            doc = apply_operation(doc, operation)
+                .map_err(|err| anyhow!("json patch operation #{idx} failed: {err}"))?;
+        }
+        Ok(doc)
+    }
+
+    /// Make a RFC 7386 merge-patch, note that merge-patch can not express setting
+    /// a member to `null` or changing single array elements.
+    pub fn merge_patch_make(left: &Value, right: &Value) -> Value {
+        match (left, right) {
+            (Value::Object(left), Value::Object(right)) => {
+                let mut patch = Map::new();
+                for (key, left_value) in left {
+                    match right.get(key) {
+                        None => {
+                            patch.insert(key.to_string(), Value::Null);
+                        }
+                        Some(right_value) if right_value != left_value => {
+                            patch.insert(
+                                key.to_string(),
+                                Self::merge_patch_make(left_value, right_value),
+                            );
+                        }
+                        _ => {}
+                    }
+                }
+                for (key, right_value) in right {
+                    if !left.contains_key(key) {
+                        if right_value.is_null() {
+                            log::warn!("merge-patch can not add null member: {key}");
+                        }
+                        patch.insert(key.to_string(), right_value.clone());
+                    }
+                }
+                Value::Object(patch)
+            }
+            (_, right) => right.clone(),
+        }
+    }
+
+    pub fn merge_patch_apply(doc: Value, patch: &Value) -> Value {
+        match patch {
+            Value::Object(patch) => {
+                let mut doc = match doc {
+                    Value::Object(doc) => doc,
+                    _ => Map::new(),
+                };
+                for (key, value) in patch {
+                    if value.is_null() {
+                        doc.remove(key);
+                    } else {
+                        let target = doc.remove(key).unwrap_or(Value::Null);
+                        doc.insert(key.to_string(), Self::merge_patch_apply(target, value));
+                    }
+                }
+                Value::Object(doc)
+            }
+            patch => patch.clone(),
+        }
+    }
+}
+
+fn json_pointer(segments: &[DiffPathSegment]) -> String {
+    segments
+        .iter()
+        .map(|segment| match segment {
+            DiffPathSegment::Key(key) => format!("/{}", key.replace('~', "~0").replace('/', "~1")),
+            DiffPathSegment::Index(idx) => format!("/{idx}"),
+            DiffPathSegment::Matched { .. } => {
+                unreachable!("json patch is made without array key matching")
+            }
+        })
+        .join("")
+}
+
+fn parse_pointer(pointer: &str) -> crate::Result<Vec<String>> {
+    if pointer.is_empty() {
+        Ok(vec![])
+    } else if let Some(pointer) = pointer.strip_prefix('/') {
+        Ok(pointer
+            .split('/')
+            .map(|it| it.replace("~1", "/").replace("~0", "~"))
+            .collect_vec())
+    } else {
+        Err(anyhow!("Invalid json pointer: {pointer}"))
+    }
+}
+
+fn apply_operation(mut doc: Value, operation: &Value) -> crate::Result<Value> {
+    let member = |name: &str| {
+        operation
+            .get(name)
+            .ok_or_else(|| anyhow!("missing member '{name}'"))
+    };
+    let pointer = |name: &str| {
+        member(name)?
+            .as_str()
+            .ok_or_else(|| anyhow!("member '{name}' must be a string"))
+            .and_then(parse_pointer)
+    };
+    let op = member("op")?
+        .as_str()
+        .ok_or_else(|| anyhow!("member 'op' must be a string"))?;
+    let path = pointer("path")?;
+    match op {
+        "add" => add(&mut doc, &path, member("value")?.clone())?,
+        "remove" => {
+            remove(&mut doc, &path)?;
+        }
+        "replace" => {
+            let target = doc
+                .pointer_mut(&to_pointer(&path))
+                .ok_or_else(|| anyhow!("path {} does not exist", to_pointer(&path)))?;
+            *target = member("value")?.clone();
+        }
+        "move" => {
+            let from = pointer("from")?;
+            if path.len() > from.len() && path.starts_with(&from) {
+                return Err(anyhow!("can not move a value into one of its children"));
+            }
+            let value = remove(&mut doc, &from)?;
+            add(&mut doc, &path, value)?;
+        }
+        "copy" => {
+            let from = to_pointer(&pointer("from")?);
+            let value = doc
+                .pointer(&from)
+                .cloned()
+                .ok_or_else(|| anyhow!("path {from} does not exist"))?;
+            add(&mut doc, &path, value)?;
+        }
+        "test" => {
+            let expected = member("value")?;
+            let actual = doc.pointer(&to_pointer(&path));
+            if actual != Some(expected) {
+                return Err(anyhow!(
+                    "test failed at {}, expect {expected}, but got {}",
+                    to_pointer(&path),
+                    actual.map(|it| it.to_string()).unwrap_or("nothing".into())
+                ));
+            }
+        }
+        op => return Err(anyhow!("unknown op '{op}'")),
+    }
+    Ok(doc)
+}
+
+fn to_pointer(tokens: &[String]) -> String {
+    tokens
+        .iter()
+        .map(|it| format!("/{}", it.replace('~', "~0").replace('/', "~1")))
+        .join("")
+}
+
+fn parent_mut<'a>(doc: &'a mut Value, path: &[String]) -> crate::Result<&'a mut Value> {
+    let parent = to_pointer(path);
+    doc.pointer_mut(&parent)
+        .ok_or_else(|| anyhow!("path {parent} does not exist"))
+}
+
+fn array_index(token: &str, len: usize) -> crate::Result<usize> {
+    let idx = token
+        .parse::<usize>()
+        .map_err(|_| anyhow!("invalid array index '{token}'"))?;
+    if idx < len {
+        Ok(idx)
+    } else {
+        Err(anyhow!("array index {idx} out of bounds"))
+    }
+}
+
+fn add(doc: &mut Value, path: &[String], value: Value) -> crate::Result<()> {
+    let Some((last, parent)) = path.split_last() else {
+        *doc = value;
+        return Ok(());
+    };
+    match parent_mut(doc, parent)? {
+        Value::Object(map) => {
+            map.insert(last.to_string(), value);
+        }
+        Value::Array(array) => {
+            if last == "-" {
+                array.push(value);
+            } else {
+                let idx = array_index(last, array.len() + 1)?;
+                array.insert(idx, value);
+            }
+        }
+        _ => return Err(anyhow!("can not add member to a scalar value")),
+    }
+    Ok(())
+}
+
+fn remove(doc: &mut Value, path: &[String]) -> crate::Result<Value> {
+    let Some((last, parent)) = path.split_last() else {
+        return Err(anyhow!("can not remove the whole document"));
+    };
+    match parent_mut(doc, parent)? {
+        Value::Object(map) => map
+            .remove(last)
+            .ok_or_else(|| anyhow!("path {} does not exist", to_pointer(path))),
+        Value::Array(array) => {
+            let idx = array_index(last, array.len())?;
+            Ok(array.remove(idx))
+        }
+        _ => Err(anyhow!("path {} does not exist", to_pointer(path))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+    use crate::command::http_parser::HttpClientOptions;
+    use crate::command::json::PatchCommand;
+    use serde_json::json;
+    use std::fs;
+    use std::str::FromStr;
+
+    #[test]
+    fn made_patch_turns_left_into_right() {
+        let left = json!({"a/b": 1, "list": [1, 2, 3, 4], "gone": true});
+        let right = json!({"a/b": 2, "list": [1, 5], "new": {"x": null}});
+        let patch = Json::patch_make(&left, &right);
+        assert_eq!(
+            patch,
+            json!([
+                {"op": "replace", "path": "/a~1b", "value": 2},
+                {"op": "remove", "path": "/gone"},
+                {"op": "replace", "path": "/list/1", "value": 5},
+                {"op": "remove", "path": "/list/3"},
+                {"op": "remove", "path": "/list/2"},
+                {"op": "add", "path": "/new", "value": {"x": null}},
+            ])
+        );
+        assert_eq!(Json::patch_apply(left, &patch).unwrap(), right);
+    }
+
+    #[test]
+    fn applied_patch_keeps_the_type_of_the_document() {
+        let dir = tempfile::tempdir().unwrap();
+        let doc = dir.path().join("config.toml");
+        fs::write(&doc, "name = \"dk\"\n").unwrap();
+        let output = dir.path().join("patched.toml");
+        let command = PatchCommand::Apply {
+            doc: Json::from_str(doc.to_str().unwrap()).unwrap(),
+            patch: Json::from_str(
+                r#"[{"op": "add", "path": "/port", "value": 8080}, {"op": "add", "path": "/debug", "value": null}]"#,
+            )
+            .unwrap(),
+            query: None,
+            query_type: None,
+            input_type: None,
+            merge: false,
+            file: Some(output.clone()),
+        };
+        command.run(&HttpClientOptions::default()).unwrap();
+        // the null is dropped with a warning instead of writing json
+        assert_eq!(
+            fs::read_to_string(&output).unwrap(),
+            "name = \"dk\"\nport = 8080\n"
+        );
+    }
+
+    #[test]
+    fn applies_move_copy_and_test_operations() {
+        let doc = json!({"a": {"b": 1}, "list": [1]});
+        let patch = json!([
+            {"op": "test", "path": "/a/b", "value": 1},
+            {"op": "copy", "from": "/a/b", "path": "/list/-"},
+            {"op": "move", "from": "/a", "path": "/c"},
+        ]);
+        assert_eq!(
+            Json::patch_apply(doc.clone(), &patch).unwrap(),
+            json!({"c": {"b": 1}, "list": [1, 1]})
+        );
+        let failed = json!([{"op": "test", "path": "/a/b", "value": 2}]);
+        assert!(Json::patch_apply(doc, &failed).is_err());
+    }
+
+    #[test]
+    fn merge_patch_round_trips() {
+        let left = json!({"a": 1, "b": {"c": 2, "d": 3}, "e": [1]});
+        let right = json!({"a": 1, "b": {"c": 4}, "e": [2]});
+        let patch = Json::merge_patch_make(&left, &right);
+        assert_eq!(patch, json!({"b": {"c": 4, "d": null}, "e": [2]}));
+        assert_eq!(Json::merge_patch_apply(left, &patch), right);
+    }
+}
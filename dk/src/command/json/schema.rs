@@ -0,0 +1,287 @@
+use super::{Json, QueryType, SchemaCommand};
+use crate::command::formatter::FormattedValue;
+use crate::command::http_parser::HttpClientOptions;
+use anyhow::anyhow;
+use itertools::Itertools;
+use serde::Serialize;
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
+use std::fs;
+
+const SCHEMA_DRAFT_2020_12: &str = "https://json-schema.org/draft/2020-12/schema";
+
+impl SchemaCommand {
+    pub fn run(&self, options: &HttpClientOptions) -> crate::Result<()> {
+        match self {
+            SchemaCommand::Infer {
+                samples,
+                input_type,
+                file,
+            } => {
+                let mut records = vec![];
+                for sample in samples {
+                    records.append(
+                        &mut sample
+                            .to_formatted_value(*input_type, options)?
+                            .into_records()?,
+                    );
+                }
+                let schema = Json::schema_infer(&records);
+                let content = serde_json::to_string_pretty(&schema)?;
+                if let Some(file) = file {
+                    fs::write(file, content)?;
+                    println!("write to {}", file.display());
+                } else {
+                    println!("{content}");
+                }
+                Ok(())
+            }
+            SchemaCommand::Validate {
+                schema,
+                doc,
+                query,
+                query_type,
+                input_type,
+            } => {
+                let schema = Value::try_from(schema.to_formatted_value(None, options)?)?;
+                let doc = doc.to_formatted_value(*input_type, options)?;
+                let violations =
+                    Json::schema_validate(&schema, &doc, query.as_deref(), *query_type)?;
+                if violations.is_empty() {
+                    println!("valid");
+                    Ok(())
+                } else {
+                    for violation in &violations {
+                        println!("{}: {}", violation.path, violation.message);
+                    }
+                    Err(anyhow!("{} schema violations found", violations.len()))
+                }
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SchemaViolation {
+    pub path: String,
+    pub schema_path: String,
+    pub message: String,
+}
+
+impl Json {
+    /// Infer a draft 2020-12 schema, every record is a sample of the same shape.
+    pub fn schema_infer(records: &[Value]) -> Value {
+        let mut shape = Shape::default();
+        for record in records {
+            shape.merge(record);
+        }
+        let mut schema = Map::new();
+        schema.insert("$schema".to_string(), json!(SCHEMA_DRAFT_2020_12));
+        if let Value::Object(inferred) = shape.to_schema() {
+            schema.extend(inferred);
+        }
+        Value::Object(schema)
+    }
+
+    pub fn schema_validate(
+        schema: &Value,
+        doc: &FormattedValue,
+        query: Option<&str>,
+        query_type: Option<QueryType>,
+    ) -> crate::Result<Vec<SchemaViolation>> {
+        let validator =
+            jsonschema::validator_for(schema).map_err(|err| anyhow!("Invalid schema: {err}"))?;
+        let doc = Self::query(doc, query, query_type)?;
+        let is_stream = matches!(
+            doc,
+            FormattedValue::Jsonl(_) | FormattedValue::YamlStream(_) | FormattedValue::Csv(_)
+        );
+        let records = doc.into_records()?;
+        let mut violations = vec![];
+        for (idx, record) in records.iter().enumerate() {
+            let root = if is_stream {
+                format!("$[{idx}]")
+            } else {
+                "$".to_string()
+            };
+            for error in validator.iter_errors(record) {
+                violations.push(SchemaViolation {
+                    path: pointer_to_jsonpath(record, &root, error.instance_path().as_str()),
+                    schema_path: error.schema_path().as_str().to_string(),
+                    message: error.to_string(),
+                });
+            }
+        }
+        Ok(violations)
+    }
+}
+
+/// Render a JSON pointer as JSONPath, the value is walked to tell array indexes
+/// from numeric object keys.
+fn pointer_to_jsonpath(value: &Value, root: &str, pointer: &str) -> String {
+    let mut path = root.to_string();
+    let mut current = Some(value);
+    for token in pointer.split('/').skip(1) {
+        let token = token.replace("~1", "/").replace("~0", "~");
+        match (current, token.parse::<usize>()) {
+            (Some(Value::Array(array)), Ok(idx)) => {
+                path = format!("{path}[{idx}]");
+                current = array.get(idx);
+            }
+            (value, _) => {
+                path = Json::jsonpath_child(&path, &token);
+                current = value.and_then(|it| it.get(&token));
+            }
+        }
+    }
+    path
+}
+
+/// Accumulated shape of all samples seen at one location.
+#[derive(Debug, Default)]
+struct Shape {
+    null: bool,
+    boolean: bool,
+    integer: bool,
+    number: bool,
+    string: bool,
+    array: Option<Box<Shape>>,
+    object: Option<ObjectShape>,
+}
+
+#[derive(Debug, Default)]
+struct ObjectShape {
+    samples: usize,
+    properties: BTreeMap<String, (usize, Shape)>,
+}
+
+impl Shape {
+    fn merge(&mut self, value: &Value) {
+        match value {
+            Value::Null => self.null = true,
+            Value::Bool(_) => self.boolean = true,
+            Value::Number(number) if number.is_i64() || number.is_u64() => self.integer = true,
+            Value::Number(_) => self.number = true,
+            Value::String(_) => self.string = true,
+            Value::Array(values) => {
+                let items = self.array.get_or_insert_with(Default::default);
+                for value in values {
+                    items.merge(value);
+                }
+            }
+            Value::Object(map) => {
+                let object = self.object.get_or_insert_with(Default::default);
+                object.samples += 1;
+                for (key, value) in map {
+                    let (count, shape) = object.properties.entry(key.to_string()).or_default();
+                    *count += 1;
+                    shape.merge(value);
+                }
+            }
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        !(self.null
+            || self.boolean
+            || self.integer
+            || self.number
+            || self.string
+            || self.array.is_some()
+            || self.object.is_some())
+    }
+
+    fn to_schema(&self) -> Value {
+        let types = [
+            (self.null, "null"),
+            (self.boolean, "boolean"),
+            (self.integer && !self.number, "integer"),
+            (self.number, "number"),
+            (self.string, "string"),
+            (self.array.is_some(), "array"),
+            (self.object.is_some(), "object"),
+        ]
+        .into_iter()
+        .filter(|(present, _)| *present)
+        .map(|(_, name)| name)
+        .collect_vec();
+        let mut schema = Map::new();
+        match types.as_slice() {
+            [] => return json!({}),
+            [single] => schema.insert("type".to_string(), json!(single)),
+            types => schema.insert("type".to_string(), json!(types)),
+        };
+        if let Some(items) = self.array.as_ref().filter(|it| !it.is_empty()) {
+            schema.insert("items".to_string(), items.to_schema());
+        }
+        if let Some(object) = &self.object {
+            let properties = object
+                .properties
+                .iter()
+                .map(|(key, (_, shape))| (key.to_string(), shape.to_schema()))
+                .collect::<Map<_, _>>();
+            let required = object
+                .properties
+                .iter()
+                .filter(|(_, (count, _))| *count == object.samples)
+                .map(|(key, _)| json!(key))
+                .collect_vec();
+            schema.insert("properties".to_string(), Value::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".to_string(), Value::Array(required));
+            }
+        }
+        Value::Object(schema)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, pointer_to_jsonpath};
+    use crate::command::formatter::FormattedValue;
+    use serde_json::json;
+
+    #[test]
+    fn infers_optional_and_required_properties_from_records() {
+        let records = vec![
+            json!({"id": 1, "name": "a", "tags": ["x"]}),
+            json!({"id": 2, "score": 1.5, "tags": []}),
+        ];
+        let schema = Json::schema_infer(&records);
+        assert_eq!(
+            schema,
+            json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "id": {"type": "integer"},
+                    "name": {"type": "string"},
+                    "score": {"type": "number"},
+                    "tags": {"type": "array", "items": {"type": "string"}}
+                },
+                "required": ["id", "tags"]
+            })
+        );
+    }
+
+    #[test]
+    fn reports_violations_with_jsonpath() {
+        let schema = json!({
+            "type": "object",
+            "properties": {"items": {"type": "array", "items": {"type": "integer"}}}
+        });
+        let doc = FormattedValue::Json(json!({"items": [1, "two"]}));
+        let violations = Json::schema_validate(&schema, &doc, None, None).unwrap();
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].path, "$.items[1]");
+    }
+
+    #[test]
+    fn keeps_numeric_object_keys_quoted() {
+        let value = json!({"100": [{"a/b": 1}]});
+        assert_eq!(
+            pointer_to_jsonpath(&value, "$", "/100/0/a~1b"),
+            "$['100'][0].a/b"
+        );
+    }
+}
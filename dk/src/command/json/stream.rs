@@ -0,0 +1,140 @@
+use super::transform::{self, JqFilter};
+use super::{Json, QueryType};
+use crate::command::formatter::FormattedValue;
+use derive_more::Display;
+use serde_json::Value;
+use std::io::{self, BufRead, Write};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Display)]
+#[display("{records} records, {matched} matched, {errors} errors")]
+pub struct StreamStats {
+    pub records: usize,
+    pub matched: usize,
+    pub errors: usize,
+}
+
+impl Json {
+    /// Query/filter a jsonl stream record by record, only the current line is
+    /// kept in memory. The jq `expr` runs first, then `query` extracts content
+    /// from each output; records without a match are skipped. Lines which fail
+    /// to parse or transform are reported to stderr and skipped.
+    pub fn stream(
+        mut reader: impl BufRead,
+        mut writer: impl Write,
+        query: Option<&str>,
+        query_type: Option<QueryType>,
+        expr: Option<&str>,
+    ) -> crate::Result<StreamStats> {
+        let filter = expr.map(transform::compile).transpose()?;
+        let query = query.filter(|it| !it.trim().is_empty());
+        let mut stats = StreamStats::default();
+        let mut line = vec![];
+        let mut line_no = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                break;
+            }
+            line_no += 1;
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            stats.records += 1;
+            let outputs = serde_json::from_slice::<Value>(&line)
+                .map_err(anyhow::Error::from)
+                .and_then(|record| apply(filter.as_ref(), record));
+            let outputs = match outputs {
+                Ok(outputs) => outputs,
+                Err(err) => {
+                    stats.errors += 1;
+                    eprintln!("line {line_no}: {err}");
+                    continue;
+                }
+            };
+            let mut matched = false;
+            for output in outputs {
+                let output = match query {
+                    Some(_) => match Self::query(&FormattedValue::Json(output), query, query_type)?
+                    {
+                        FormattedValue::Json(value) if !is_empty_match(&value) => value,
+                        _ => continue,
+                    },
+                    None => output,
+                };
+                serde_json::to_writer(&mut writer, &output).map_err(io::Error::from)?;
+                writer.write_all(b"\n")?;
+                matched = true;
+            }
+            if matched {
+                stats.matched += 1;
+            }
+        }
+        writer.flush()?;
+        Ok(stats)
+    }
+}
+
+fn apply(filter: Option<&JqFilter>, record: Value) -> crate::Result<Vec<Value>> {
+    match filter {
+        Some(filter) => transform::run(filter, record),
+        None => Ok(vec![record]),
+    }
+}
+
+fn is_empty_match(value: &Value) -> bool {
+    match value {
+        Value::Null => true,
+        Value::Array(values) => values.is_empty(),
+        Value::Object(map) => map.is_empty(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, StreamStats};
+    use std::io::Cursor;
+
+    #[test]
+    fn filters_records_and_skips_invalid_lines() {
+        let input =
+            "{\"level\":\"info\",\"msg\":\"a\"}\nnot json\n\n{\"level\":\"error\",\"msg\":\"b\"}\n";
+        let mut output = vec![];
+        let stats = Json::stream(
+            Cursor::new(input),
+            &mut output,
+            None,
+            None,
+            Some(r#"select(.level == "error")"#),
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "{\"level\":\"error\",\"msg\":\"b\"}\n"
+        );
+        assert_eq!(
+            stats,
+            StreamStats {
+                records: 3,
+                matched: 1,
+                errors: 1
+            }
+        );
+    }
+
+    #[test]
+    fn extracts_query_from_each_record() {
+        let input = "{\"req\":{\"id\":1}}\n{\"other\":true}\n{\"req\":{\"id\":2}}";
+        let mut output = vec![];
+        let stats = Json::stream(
+            Cursor::new(input),
+            &mut output,
+            Some("$.req.id"),
+            None,
+            None,
+        )
+        .unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), "1\n2\n");
+        assert_eq!(stats.matched, 2);
+    }
+}
@@ -0,0 +1,170 @@
+use super::Json;
+use crate::command::formatter::{FormattedValue, FormattedValueType};
+use anyhow::anyhow;
+use itertools::Itertools;
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+
+impl Json {
+    /// Run a jq expression (the dialect of [jaq](https://github.com/01mf02/jaq))
+    /// on the input. Records of a jsonl/yaml stream or csv rows are transformed one by one
+    /// unless `slurp` is set, in which case the expression sees one array.
+    pub fn transform(
+        input: &FormattedValue,
+        expr: &str,
+        slurp: bool,
+    ) -> crate::Result<FormattedValue> {
+        let filter = compile(expr)?;
+        let per_record = !slurp
+            && matches!(
+                input,
+                FormattedValue::Jsonl(_) | FormattedValue::YamlStream(_) | FormattedValue::Csv(_)
+            );
+        let records = if per_record {
+            input.clone().into_records()?
+        } else {
+            vec![Value::try_from(input)?]
+        };
+        let mut outputs = vec![];
+        for (idx, record) in records.into_iter().enumerate() {
+            let mut values = run(&filter, record).map_err(|err| {
+                if per_record {
+                    anyhow!("transform record {} failed: {err}", idx + 1)
+                } else {
+                    anyhow!("transform failed: {err}")
+                }
+            })?;
+            outputs.append(&mut values);
+        }
+        match (per_record, outputs.len(), input.type_()) {
+            (
+                false,
+                1,
+                FormattedValueType::Text
+                | FormattedValueType::Jsonl
+                | FormattedValueType::YamlStream
+                | FormattedValueType::Csv
+                | FormattedValueType::Tsv,
+            ) => Ok(FormattedValue::Json(outputs.remove(0))),
+            (false, 1, type_) => {
+                let output = outputs.remove(0);
+                Ok(FormattedValue::Json(output.clone())
+                    .convert(type_)
+                    .unwrap_or(FormattedValue::Json(output)))
+            }
+            _ => Ok(FormattedValue::Jsonl(outputs)),
+        }
+    }
+}
+
+pub(super) type JqFilter = jaq_core::Filter<Native<Val>>;
+
+pub(super) fn run(filter: &JqFilter, value: Value) -> crate::Result<Vec<Value>> {
+    let inputs = RcIter::new(core::iter::empty());
+    filter
+        .run((Ctx::new([], &inputs), Val::from(value)))
+        .map(|output| output.map(Value::from).map_err(|err| anyhow!("{err}")))
+        .collect()
+}
+
+pub(super) fn compile(expr: &str) -> crate::Result<JqFilter> {
+    let program = File {
+        code: expr,
+        path: (),
+    };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+    let modules = loader.load(&arena, program).map_err(|errors| {
+        let messages = errors
+            .into_iter()
+            .flat_map(|(_, error)| match error {
+                jaq_core::load::Error::Io(errors) => errors
+                    .into_iter()
+                    .map(|(path, error)| format!("{path}: {error}"))
+                    .collect_vec(),
+                jaq_core::load::Error::Lex(errors) => errors
+                    .into_iter()
+                    .map(|(expect, found)| {
+                        format!(
+                            "expected {} at column {}",
+                            expect.as_str(),
+                            column(expr, found)
+                        )
+                    })
+                    .collect_vec(),
+                jaq_core::load::Error::Parse(errors) => errors
+                    .into_iter()
+                    .map(|(expect, found)| {
+                        format!(
+                            "expected {} at column {}",
+                            expect.as_str(),
+                            column(expr, found)
+                        )
+                    })
+                    .collect_vec(),
+            })
+            .join(", ");
+        anyhow!("Invalid jq expression: {messages}")
+    })?;
+    Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errors| {
+            let messages = errors
+                .into_iter()
+                .flat_map(|(_, errors)| errors)
+                .map(|(name, undefined)| format!("undefined {} '{name}'", undefined.as_str()))
+                .join(", ");
+            anyhow!("Invalid jq expression: {messages}")
+        })
+}
+
+/// `found` is the remaining part of `expr` where the error was detected.
+fn column(expr: &str, found: &str) -> usize {
+    expr.len().saturating_sub(found.len()) + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Json;
+    use crate::command::formatter::FormattedValue;
+    use serde_json::json;
+
+    #[test]
+    fn projects_groups_and_sorts() {
+        let input = FormattedValue::Json(json!([
+            {"kind": "b", "n": 2},
+            {"kind": "a", "n": 1},
+            {"kind": "b", "n": 3}
+        ]));
+        let output = Json::transform(
+            &input,
+            "group_by(.kind) | map({kind: .[0].kind, total: (map(.n) | add)}) | sort_by(-.total)",
+            false,
+        )
+        .unwrap();
+        assert!(matches!(
+            output,
+            FormattedValue::Json(value) if value == json!([{"kind": "b", "total": 5}, {"kind": "a", "total": 1}])
+        ));
+    }
+
+    #[test]
+    fn transforms_jsonl_records_one_by_one() {
+        let input =
+            FormattedValue::Jsonl(vec![json!({"msg": "a", "ok": true}), json!({"msg": "b"})]);
+        let output = Json::transform(&input, "select(.ok) | .msg | ascii_upcase", false).unwrap();
+        assert!(matches!(output, FormattedValue::Jsonl(values) if values == vec![json!("A")]));
+        let output = Json::transform(&input, "length", true).unwrap();
+        assert!(matches!(output, FormattedValue::Json(value) if value == json!(2)));
+    }
+
+    #[test]
+    fn reports_invalid_expressions() {
+        let input = FormattedValue::Json(json!({}));
+        assert!(Json::transform(&input, ".a | (", false).is_err());
+        assert!(Json::transform(&input, "no_such_fn", false).is_err());
+    }
+}
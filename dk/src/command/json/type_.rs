@@ -1,4 +1,5 @@
-use crate::command::http_parser::HttpRequest;
+use crate::command::formatter::{FormattedValueType, parse_file_content, parse_formatted_value_as};
+use crate::command::http_parser::{Har, HttpClientOptions, HttpRequest};
 use crate::command::json::{FormattedValue, Json, KeyPatternType, QueryType};
 use crate::command::read_stdin;
 use anyhow::{Context, anyhow};
@@ -8,28 +9,69 @@ use std::path::PathBuf;
 use std::process::Command;
 use std::str::FromStr;
 
-impl TryFrom<&Json> for FormattedValue {
-    type Error = anyhow::Error;
+impl FromStr for FormattedValue {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(super::parse_formatted_value(s))
+    }
+}
+
+impl FromStr for FormattedValueType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "json" => Ok(Self::Json),
+            "jsonl" | "ndjson" => Ok(Self::Jsonl),
+            "yaml" | "yml" => Ok(Self::Yaml),
+            "yamlstream" | "yaml-stream" => Ok(Self::YamlStream),
+            "toml" => Ok(Self::Toml),
+            "csv" => Ok(Self::Csv),
+            "tsv" => Ok(Self::Tsv),
+            "xml" => Ok(Self::Xml),
+            "text" | "txt" => Ok(Self::Text),
+            _ => Err(anyhow!(
+                "Invalid input type: {s}, expects json/jsonl/yaml/yamlstream/toml/csv/tsv/xml/text"
+            )),
+        }
+    }
+}
 
-    fn try_from(input: &Json) -> Result<Self, Self::Error> {
-        let json = match input {
+impl Json {
+    /// Resolve the content of the input, urls are fetched with `options` and
+    /// `input_type` forces the parser instead of guessing the type.
+    pub fn to_formatted_value(
+        &self,
+        input_type: Option<FormattedValueType>,
+        options: &HttpClientOptions,
+    ) -> crate::Result<FormattedValue> {
+        if let Some(input_type) = input_type {
+            return parse_formatted_value_as(&self.text(options)?, input_type);
+        }
+        let json = match self {
             Json::Cmd(input) | Json::String(input) => super::parse_formatted_value(input),
             Json::Filepath(path) => {
                 let input = fs::read_to_string(path)
                     .with_context(|| format!("read file {} failed", path.display()))?;
-                super::parse_formatted_value(&input)
+                parse_file_content(path, &input)
             }
-            Json::HttpRequest(http_request) => http_request.try_into()?,
+            Json::HttpRequest(http_request) => http_request.to_formatted_value(options)?,
+            Json::Har(har) => FormattedValue::Json(har.to_value()),
         };
         Ok(json)
     }
-}
 
-impl FromStr for FormattedValue {
-    type Err = anyhow::Error;
-
-    fn from_str(s: &str) -> Result<Self, Self::Err> {
-        Ok(super::parse_formatted_value(s))
+    /// The raw content of the input.
+    pub fn text(&self, options: &HttpClientOptions) -> crate::Result<String> {
+        match self {
+            Json::Cmd(input) | Json::String(input) => Ok(input.clone()),
+            Json::Filepath(path) => fs::read_to_string(path)
+                .with_context(|| format!("read file {} failed", path.display())),
+            Json::HttpRequest(http_request) => http_request.text(options),
+            Json::Har(har) => fs::read_to_string(har.path())
+                .with_context(|| format!("read file {} failed", har.path().display())),
+        }
     }
 }
 
@@ -69,7 +111,11 @@ impl FromStr for Json {
                 Err(anyhow!("Not a valid path: {}", value))
             }
         } {
-            Ok(Json::Filepath(path))
+            if Har::is_har(&path) {
+                Ok(Json::Har(Har::load(&path)?))
+            } else {
+                Ok(Json::Filepath(path))
+            }
         } else {
             Ok(Json::String(value.to_string()))
         }
@@ -116,6 +162,7 @@ impl FromStr for QueryType {
         let s = s.to_lowercase();
         match s.as_str() {
             "jsonpath" | "jp" => Ok(Self::JsonPath),
+            "xpath" | "xp" => Ok(Self::XPath),
             _ => Ok(Self::KeyPattern(
                 KeyPatternType::from_str(s.as_str())
                     .map_err(|err| anyhow!("Invalid query type: {}", err))?,
@@ -0,0 +1,390 @@
+//! XML support of the formatter.
+//!
+//! XML is mapped to JSON as follows:
+//! - the document is an object keyed by the name of its root element;
+//! - attributes are keys prefixed with `@`, namespace declarations included
+//!   (`@xmlns`, `@xmlns:soap`);
+//! - an element without attributes and children is its text, or `null` when
+//!   empty; otherwise its text is kept under `#text`;
+//! - repeated child elements with the same name become an array;
+//! - prefixes are kept in names (`soap:Body`), all values are strings,
+//!   comments and processing instructions are dropped.
+//!
+//! The mapping is reversed when JSON is converted to XML, a value which is not
+//! an object with a single key is wrapped in a `<root>` element.
+use super::Json;
+use crate::command::formatter::{line_column, located_error};
+use anyhow::anyhow;
+use itertools::Itertools;
+use quick_xml::events::{BytesDecl, BytesEnd, BytesStart, BytesText, Event};
+use quick_xml::{Reader, Writer};
+use serde_json::{Map, Value};
+use sxd_document::dom::{ChildOfElement, ChildOfRoot, Element, Root};
+use sxd_document::{Package, parser};
+use sxd_xpath::nodeset::Node;
+use sxd_xpath::{Context, Factory};
+
+const TEXT_KEY: &str = "#text";
+const ROOT_NAME: &str = "root";
+/// Prefix bound to the default namespace of the root element in XPath queries,
+/// eg. `/_:project/_:version` on a Maven POM.
+const DEFAULT_NAMESPACE_PREFIX: &str = "_";
+
+impl Json {
+    /// Evaluate an XPath 1.0 expression, prefixes declared in the document can
+    /// be used in the expression. Selected nodes are mapped to JSON, a single
+    /// node is returned as is.
+    pub fn xpath(xml: &str, query: &str) -> crate::Result<Value> {
+        let package = parse(xml)?;
+        let document = package.as_document();
+        let mut context = Context::new();
+        for element in root_elements(document.root()) {
+            if let Some(uri) = element.default_namespace_uri() {
+                context.set_namespace(DEFAULT_NAMESPACE_PREFIX, uri);
+            }
+            register_namespaces(element, &mut context);
+        }
+        let xpath = Factory::new()
+            .build(query)
+            .map_err(|err| anyhow!("Invalid xpath {query}: {err}"))?
+            .ok_or_else(|| anyhow!("Invalid xpath {query}"))?;
+        let value = xpath
+            .evaluate(&context, document.root())
+            .map_err(|err| anyhow!("Evaluate xpath {query} failed: {err}"))?;
+        match value {
+            sxd_xpath::Value::Boolean(value) => Ok(Value::Bool(value)),
+            sxd_xpath::Value::Number(value) => Ok(number_to_value(value)),
+            sxd_xpath::Value::String(value) => Ok(Value::String(value)),
+            sxd_xpath::Value::Nodeset(nodes) => {
+                let mut values = nodes
+                    .document_order()
+                    .into_iter()
+                    .map(node_to_value)
+                    .collect_vec();
+                if values.len() == 1 {
+                    Ok(values.remove(0))
+                } else {
+                    Ok(Value::Array(values))
+                }
+            }
+        }
+    }
+}
+
+pub(crate) fn validate(xml: &str) -> crate::Result<()> {
+    parse(xml).map(|_| ())
+}
+
+pub(crate) fn xml_to_value(xml: &str) -> crate::Result<Value> {
+    let package = parse(xml)?;
+    Ok(root_to_value(package.as_document().root()))
+}
+
+pub(crate) fn value_to_xml(value: &Value) -> crate::Result<String> {
+    let mut writer = Writer::new_with_indent(vec![], b' ', 2);
+    writer.write_event(Event::Decl(BytesDecl::new("1.0", Some("UTF-8"), None)))?;
+    match value {
+        Value::Object(map) if map.len() == 1 => {
+            let (name, value) = map.iter().next().expect("unexpected empty map");
+            if value.is_array() || name.starts_with('@') || name == TEXT_KEY {
+                write_element(&mut writer, ROOT_NAME, value)?;
+            } else {
+                write_element(&mut writer, name, value)?;
+            }
+        }
+        value => write_element(&mut writer, ROOT_NAME, value)?,
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+/// Re-indent the document, whitespace only text between elements is dropped.
+pub(crate) fn reformat(xml: &str, pretty: bool) -> crate::Result<String> {
+    let mut reader = Reader::from_str(xml);
+    let mut writer = if pretty {
+        Writer::new_with_indent(vec![], b' ', 2)
+    } else {
+        Writer::new(vec![])
+    };
+    loop {
+        match reader.read_event()? {
+            Event::Eof => break,
+            Event::Text(text) if text.iter().all(u8::is_ascii_whitespace) => {}
+            event => writer.write_event(event)?,
+        }
+    }
+    Ok(String::from_utf8(writer.into_inner())?)
+}
+
+fn parse(xml: &str) -> crate::Result<Package> {
+    parser::parse(xml).map_err(|err| {
+        let (line, column) = line_column(xml, err.location());
+        let message = err.to_string();
+        let message = message
+            .split_once(": ")
+            .map_or(message.as_str(), |it| it.1)
+            .trim_matches(['{', '}']);
+        located_error("XML", message.to_string(), line, column)
+    })
+}
+
+fn root_elements(root: Root) -> Vec<Element> {
+    root.children()
+        .into_iter()
+        .filter_map(ChildOfRoot::element)
+        .collect()
+}
+
+fn register_namespaces(element: Element, context: &mut Context) {
+    for namespace in element.namespaces_in_scope() {
+        context.set_namespace(namespace.prefix(), namespace.uri());
+    }
+    for child in element.children() {
+        if let ChildOfElement::Element(child) = child {
+            register_namespaces(child, context);
+        }
+    }
+}
+
+fn root_to_value(root: Root) -> Value {
+    let mut map = Map::new();
+    for element in root_elements(root) {
+        insert_child(&mut map, element_name(element), element_to_value(element));
+    }
+    Value::Object(map)
+}
+
+fn element_to_value(element: Element) -> Value {
+    let mut map = Map::new();
+    if let Some(uri) = element.default_namespace_uri() {
+        map.insert("@xmlns".to_string(), Value::String(uri.to_string()));
+    }
+    let inherited = element
+        .parent()
+        .and_then(|it| it.element())
+        .map(|it| it.namespaces_in_scope())
+        .unwrap_or_default();
+    for namespace in element.namespaces_in_scope() {
+        if namespace.prefix() != "xml"
+            && !inherited
+                .iter()
+                .any(|it| it.prefix() == namespace.prefix() && it.uri() == namespace.uri())
+        {
+            map.insert(
+                format!("@xmlns:{}", namespace.prefix()),
+                Value::String(namespace.uri().to_string()),
+            );
+        }
+    }
+    for attribute in element.attributes() {
+        let name = qualified_name(attribute.preferred_prefix(), attribute.name().local_part());
+        map.insert(
+            format!("@{name}"),
+            Value::String(attribute.value().to_string()),
+        );
+    }
+    let mut text = String::new();
+    for child in element.children() {
+        match child {
+            ChildOfElement::Element(child) => {
+                insert_child(&mut map, element_name(child), element_to_value(child))
+            }
+            ChildOfElement::Text(child) => text.push_str(child.text()),
+            ChildOfElement::Comment(_) | ChildOfElement::ProcessingInstruction(_) => {}
+        }
+    }
+    let text = text.trim();
+    match (map.is_empty(), text.is_empty()) {
+        (true, true) => Value::Null,
+        (true, false) => Value::String(text.to_string()),
+        (false, empty) => {
+            if !empty {
+                map.insert(TEXT_KEY.to_string(), Value::String(text.to_string()));
+            }
+            Value::Object(map)
+        }
+    }
+}
+
+/// Repeated elements are collected into an array, an element itself never maps
+/// to an array.
+fn insert_child(map: &mut Map<String, Value>, name: String, value: Value) {
+    match map.get_mut(&name) {
+        Some(Value::Array(values)) => values.push(value),
+        Some(existing) => *existing = Value::Array(vec![existing.take(), value]),
+        None => {
+            map.insert(name, value);
+        }
+    }
+}
+
+fn element_name(element: Element) -> String {
+    qualified_name(element.preferred_prefix(), element.name().local_part())
+}
+
+fn qualified_name(prefix: Option<&str>, local_part: &str) -> String {
+    match prefix {
+        Some(prefix) => format!("{prefix}:{local_part}"),
+        None => local_part.to_string(),
+    }
+}
+
+fn node_to_value(node: Node) -> Value {
+    match node {
+        Node::Root(root) => root_to_value(root),
+        Node::Element(element) => {
+            let mut map = Map::new();
+            map.insert(element_name(element), element_to_value(element));
+            Value::Object(map)
+        }
+        Node::Attribute(attribute) => Value::String(attribute.value().to_string()),
+        Node::Text(text) => Value::String(text.text().to_string()),
+        Node::Comment(comment) => Value::String(comment.text().to_string()),
+        Node::Namespace(namespace) => Value::String(namespace.uri().to_string()),
+        Node::ProcessingInstruction(pi) => {
+            Value::String(pi.value().unwrap_or_default().to_string())
+        }
+    }
+}
+
+fn number_to_value(value: f64) -> Value {
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::from(value as i64)
+    } else {
+        serde_json::Number::from_f64(value)
+            .map(Value::Number)
+            .unwrap_or(Value::Null)
+    }
+}
+
+fn write_element(writer: &mut Writer<Vec<u8>>, name: &str, value: &Value) -> crate::Result<()> {
+    if !is_xml_name(name) {
+        return Err(anyhow!("Can not convert key {name} to an XML element name"));
+    }
+    match value {
+        Value::Array(values) => {
+            for value in values {
+                write_element(writer, name, value)?;
+            }
+        }
+        Value::Object(map) => {
+            let mut start = BytesStart::new(name);
+            for (key, value) in map {
+                if let Some(attribute) = key.strip_prefix('@') {
+                    start.push_attribute((attribute, scalar_text(value).as_str()));
+                }
+            }
+            let children = map
+                .iter()
+                .filter(|(key, _)| !key.starts_with('@'))
+                .collect_vec();
+            if children.is_empty() {
+                writer.write_event(Event::Empty(start))?;
+            } else {
+                writer.write_event(Event::Start(start))?;
+                for (key, value) in children {
+                    if key == TEXT_KEY {
+                        writer.write_event(Event::Text(BytesText::new(&scalar_text(value))))?;
+                    } else {
+                        write_element(writer, key, value)?;
+                    }
+                }
+                writer.write_event(Event::End(BytesEnd::new(name)))?;
+            }
+        }
+        Value::Null => writer.write_event(Event::Empty(BytesStart::new(name)))?,
+        value => {
+            writer.write_event(Event::Start(BytesStart::new(name)))?;
+            writer.write_event(Event::Text(BytesText::new(&scalar_text(value))))?;
+            writer.write_event(Event::End(BytesEnd::new(name)))?;
+        }
+    }
+    Ok(())
+}
+
+fn scalar_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
+
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|it| it.is_alphabetic() || it == '_')
+        && chars.all(|it| it.is_alphanumeric() || matches!(it, '_' | '-' | '.' | ':'))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Json, reformat, value_to_xml, xml_to_value};
+    use crate::command::formatter::FormattedValue;
+    use serde_json::json;
+
+    const POM: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<project xmlns="http://maven.apache.org/POM/4.0.0">
+  <version>1.0</version>
+  <dependencies>
+    <dependency scope="test"><artifactId>junit</artifactId></dependency>
+    <dependency><artifactId>serde &amp; co</artifactId></dependency>
+  </dependencies>
+</project>"#;
+
+    #[test]
+    fn maps_attributes_text_and_repeated_elements() {
+        let value = xml_to_value(POM).unwrap();
+        assert_eq!(
+            value,
+            json!({"project": {
+                "@xmlns": "http://maven.apache.org/POM/4.0.0",
+                "version": "1.0",
+                "dependencies": {"dependency": [
+                    {"@scope": "test", "artifactId": "junit"},
+                    {"artifactId": "serde & co"}
+                ]}
+            }})
+        );
+        let xml = value_to_xml(&value).unwrap();
+        assert_eq!(xml_to_value(&xml).unwrap(), value);
+    }
+
+    #[test]
+    fn queries_with_xpath_and_namespace_prefixes() {
+        assert_eq!(
+            Json::xpath(POM, "/_:project/_:version/text()").unwrap(),
+            json!("1.0")
+        );
+        assert_eq!(Json::xpath(POM, "count(//_:dependency)").unwrap(), json!(2));
+        let soap = r#"<soap:Envelope xmlns:soap="http://www.w3.org/2003/05/soap-envelope"><soap:Body><price currency="EUR">12.5</price></soap:Body></soap:Envelope>"#;
+        assert_eq!(
+            Json::xpath(soap, "//soap:Body/price").unwrap(),
+            json!({"price": {"@currency": "EUR", "#text": "12.5"}})
+        );
+        assert!(Json::xpath(soap, "//[").is_err());
+    }
+
+    #[test]
+    fn uses_xpath_only_for_path_like_queries() {
+        let input = FormattedValue::Xml(POM.to_string());
+        let query = |query: &str| match Json::query(&input, Some(query), None).unwrap() {
+            FormattedValue::Json(value) => value,
+            _ => unreachable!(),
+        };
+        assert_eq!(query("(//_:artifactId)[1]/text()"), json!("junit"));
+        assert_eq!(query("//_:version/text()"), json!("1.0"));
+        assert_eq!(query("$.project.version"), json!("1.0"));
+        assert_ne!(query("artifactId"), json!([]));
+    }
+
+    #[test]
+    fn pretty_prints_without_touching_text() {
+        let xml = "<a><b>x &amp; y</b>\n\n<c/></a>";
+        assert_eq!(
+            reformat(xml, true).unwrap(),
+            "<a>\n  <b>x &amp; y</b>\n  <c/>\n</a>"
+        );
+        assert_eq!(reformat(xml, false).unwrap(), "<a><b>x &amp; y</b><c/></a>");
+    }
+}
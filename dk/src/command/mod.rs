@@ -13,11 +13,20 @@ pub enum Commands {
     Json {
         #[clap(subcommand)]
         command: json::JsonCommand,
+        #[command(flatten)]
+        client: http::HttpClientArgs,
+    },
+    #[clap(about = "http tools, run jetbrains .http files")]
+    Http {
+        #[clap(subcommand)]
+        command: http::HttpCommand,
+        #[command(flatten)]
+        client: http::HttpClientArgs,
     },
     #[clap(about = "time tools")]
     Time {
         #[clap(subcommand)]
-        command: time::TimeCommand,
+        command: time::TimeSubcommand,
     },
     #[clap(
         name = "qrcode",
@@ -25,6 +34,11 @@ pub enum Commands {
         alias = "qr"
     )]
     QrCode(qrcode::QrCodeArgs),
+    #[clap(
+        about = "convert between json/jsonl/yaml/toml/csv/xml, alias 'cv'",
+        alias = "cv"
+    )]
+    Convert(convert::ConvertArgs),
     #[clap(about = "base64 tools, alias 'b64'", alias = "b64")]
     Base64 {
         #[clap(subcommand)]
@@ -40,16 +54,20 @@ impl Command for Commands {
     fn run(&self) -> crate::Result<()> {
         match self {
             Commands::Uri { command } => command.run(),
-            Commands::Json { command } => command.run(),
+            Commands::Json { command, client } => command.run(&client.options()?),
+            Commands::Http { command, client } => command.run(&client.options()?),
             Commands::Time { command } => command.run(),
             Commands::QrCode(args) => args.run(),
+            Commands::Convert(args) => args.run(),
             Commands::Base64 { command } => command.run(),
         }
     }
 }
 
 pub mod base64;
+pub mod convert;
 pub mod formatter;
+pub mod http;
 mod http_parser;
 pub mod json;
 pub mod qrcode;
@@ -0,0 +1,263 @@
+use anyhow::anyhow;
+use chrono::{DateTime, SubsecRound, TimeZone, Utc};
+use croner::Cron;
+use croner::errors::CronError;
+use croner::parser::{CronParser, Seconds, Year};
+use derive_more::Display;
+use serde::Serialize;
+
+/// The cron syntaxes, told apart by their number of fields unless given.
+#[derive(Debug, Copy, Clone, Display, PartialEq, Serialize)]
+pub enum CronDialect {
+    /// `minute hour day-of-month month day-of-week`
+    #[display("unix")]
+    #[serde(rename = "unix")]
+    Unix,
+    /// A leading second, days of the week 0-7 from SUN, both day fields must match
+    #[display("spring")]
+    #[serde(rename = "spring")]
+    Spring,
+    /// A leading second and an optional year, days of the week 1-7 from SUN, `?` in one day field
+    #[display("quartz")]
+    #[serde(rename = "quartz")]
+    Quartz,
+}
+
+impl std::str::FromStr for CronDialect {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "unix" | "standard" | "5" => Ok(CronDialect::Unix),
+            "spring" => Ok(CronDialect::Spring),
+            "quartz" => Ok(CronDialect::Quartz),
+            _ => Err(anyhow!(
+                "Invalid cron dialect: {s}, expected unix, spring or quartz"
+            )),
+        }
+    }
+}
+
+impl CronDialect {
+    /// 5 fields and `@daily`-like nicknames are unix, 6 spring and 7 quartz.
+    fn detect(expression: &str) -> crate::Result<Self> {
+        if expression.starts_with('@') {
+            return Ok(CronDialect::Unix);
+        }
+        match expression.split_whitespace().count() {
+            5 => Ok(CronDialect::Unix),
+            6 => Ok(CronDialect::Spring),
+            7 => Ok(CronDialect::Quartz),
+            count => Err(anyhow!(
+                "Invalid cron expression: {expression}, {count} fields, expected 5 (unix), 6 (spring) or 7 (quartz)"
+            )),
+        }
+    }
+
+    fn field_names(&self) -> &'static [&'static str] {
+        const NAMES: [&str; 7] = [
+            "second",
+            "minute",
+            "hour",
+            "day-of-month",
+            "month",
+            "day-of-week",
+            "year",
+        ];
+        match self {
+            CronDialect::Unix => &NAMES[1..6],
+            CronDialect::Spring => &NAMES[..6],
+            CronDialect::Quartz => &NAMES,
+        }
+    }
+
+    fn parser(&self) -> CronParser {
+        match self {
+            CronDialect::Unix => CronParser::builder()
+                .seconds(Seconds::Disallowed)
+                .year(Year::Disallowed),
+            CronDialect::Spring => CronParser::builder()
+                .seconds(Seconds::Required)
+                .year(Year::Disallowed)
+                .dom_and_dow(true),
+            CronDialect::Quartz => CronParser::builder()
+                .seconds(Seconds::Required)
+                .year(Year::Optional)
+                .alternative_weekdays(true),
+        }
+        .build()
+    }
+}
+
+/// A validated cron expression.
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    expression: String,
+    dialect: CronDialect,
+    detected: bool,
+    cron: Cron,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str, dialect: Option<CronDialect>) -> crate::Result<Self> {
+        let expression = expression.trim().to_string();
+        if expression.is_empty() {
+            return Err(anyhow!("Invalid cron expression: empty"));
+        }
+        let detected = dialect.is_none();
+        let dialect = match dialect {
+            Some(dialect) => dialect,
+            None => CronDialect::detect(&expression)?,
+        };
+        if dialect == CronDialect::Quartz && !expression.starts_with('@') {
+            let fields: Vec<_> = expression.split_whitespace().collect();
+            if fields.len() >= 6 && (fields[3] == "?") == (fields[5] == "?") {
+                return Err(anyhow!(
+                    "Invalid quartz expression: {expression}, one of day-of-month and day-of-week must be `?`, e.g. `0 0 12 ? * MON`"
+                ));
+            }
+        }
+        let cron = dialect
+            .parser()
+            .parse(&expression)
+            .map_err(|err| anyhow!("Invalid {dialect} cron expression: {expression}, {err}"))?;
+        Ok(Self {
+            expression,
+            dialect,
+            detected,
+            cron,
+        })
+    }
+
+    pub fn dialect(&self) -> CronDialect {
+        self.dialect
+    }
+
+    /// The fields by name, nicknames as `@daily` have none.
+    pub fn fields(&self) -> Vec<(String, String)> {
+        if self.expression.starts_with('@') {
+            return vec![];
+        }
+        self.dialect
+            .field_names()
+            .iter()
+            .zip(self.expression.split_whitespace())
+            .map(|(name, value)| (name.to_string(), value.to_string()))
+            .collect()
+    }
+
+    pub fn description(&self) -> String {
+        self.cron.describe()
+    }
+
+    /// Readings of the expression that commonly differ from what was meant.
+    pub fn notes(&self) -> Vec<String> {
+        let fields = self.fields();
+        let field = |name: &str| {
+            fields
+                .iter()
+                .find(|(it, _)| it == name)
+                .map(|(_, value)| value.as_str())
+        };
+        let mut notes = vec![];
+        if self.detected
+            && self.dialect == CronDialect::Spring
+            && field("day-of-week").is_some_and(|it| it.chars().any(|c| c.is_ascii_digit()))
+        {
+            notes.push(
+                "6 fields are read as spring, day-of-week 0-7 from SUN; quartz numbers them 1-7 from SUN, use --dialect quartz"
+                    .to_string(),
+            );
+        }
+        if field("second") == Some("*") {
+            notes.push(
+                "the first field is the second, `*` runs every second of the matching minutes"
+                    .to_string(),
+            );
+        }
+        let restricted = |name: &str| field(name).is_some_and(|it| it != "*" && it != "?");
+        if restricted("day-of-month") && restricted("day-of-week") {
+            notes.push(match self.dialect {
+                CronDialect::Unix => {
+                    "runs on days matching the day-of-month OR the day-of-week".to_string()
+                }
+                _ => "runs on days matching both the day-of-month AND the day-of-week".to_string(),
+            });
+        }
+        notes
+    }
+
+    /// The next `count` runs after `from`, following the calendar and DST of `zone`.
+    pub fn next_runs<Z: TimeZone>(
+        &self,
+        from: &DateTime<Utc>,
+        zone: &Z,
+        count: usize,
+    ) -> crate::Result<Vec<DateTime<Utc>>> {
+        let mut runs = vec![];
+        // runs are on whole seconds
+        let mut time = from.trunc_subsecs(0).with_timezone(zone);
+        while runs.len() < count {
+            time = match self.cron.find_next_occurrence(&time, false) {
+                Ok(time) => time,
+                Err(CronError::TimeSearchLimitExceeded) if runs.is_empty() => {
+                    return Err(anyhow!("{} never runs", self.expression));
+                }
+                Err(CronError::TimeSearchLimitExceeded) => break,
+                Err(err) => return Err(anyhow!("{} failed: {err}", self.expression)),
+            };
+            runs.push(time.to_utc());
+        }
+        Ok(runs)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CronDialect, CronSchedule};
+    use chrono::{DateTime, Utc};
+
+    #[test]
+    fn parses_every_dialect() {
+        let from: DateTime<Utc> = "2026-01-01T00:00:00.250Z".parse().unwrap();
+        let runs = |expression: &str, dialect: Option<CronDialect>| {
+            let schedule = CronSchedule::parse(expression, dialect).unwrap();
+            let runs = schedule.next_runs(&from, &Utc, 2).unwrap();
+            let runs: Vec<_> = runs.iter().map(|it| it.to_rfc3339()).collect();
+            (schedule.dialect(), runs)
+        };
+        // Friday January 2nd
+        let friday = "2026-01-02T09:30:00+00:00".to_string();
+        let (dialect, unix) = runs("30 9 * * 5", None);
+        assert_eq!(dialect, CronDialect::Unix);
+        assert_eq!(unix[0], friday);
+        let (dialect, spring) = runs("0 30 9 * * FRI", None);
+        assert_eq!(dialect, CronDialect::Spring);
+        assert_eq!(spring, unix);
+        // quartz numbers the days from SUN = 1
+        let (dialect, quartz) = runs("0 30 9 ? * 6 2026", None);
+        assert_eq!(dialect, CronDialect::Quartz);
+        assert_eq!(quartz, unix);
+        let (_, quartz) = runs("0 30 9 ? * 6", Some(CronDialect::Quartz));
+        assert_eq!(quartz, unix);
+        assert!(CronSchedule::parse("0 30 9 * * 6 2026", None).is_err());
+        assert!(CronSchedule::parse("30 9 * *", None).is_err());
+        assert!(CronSchedule::parse("61 9 * * *", None).is_err());
+    }
+
+    #[test]
+    fn notes_common_mistakes() {
+        let notes = CronSchedule::parse("* 5 * * * 1", None).unwrap().notes();
+        assert_eq!(notes.len(), 2);
+        assert!(notes[0].contains("--dialect quartz"));
+        assert!(notes[1].contains("every second"));
+        let notes = CronSchedule::parse("0 0 13 * 5", None).unwrap().notes();
+        assert_eq!(
+            notes,
+            ["runs on days matching the day-of-month OR the day-of-week"]
+        );
+        let schedule = CronSchedule::parse("0 0 30 2 *", None).unwrap();
+        let from = "2026-01-01T00:00:00Z".parse().unwrap();
+        assert!(schedule.next_runs(&from, &Utc, 1).is_err());
+    }
+}
@@ -0,0 +1,234 @@
+use anyhow::anyhow;
+use chrono::TimeDelta;
+use derive_more::Deref;
+use lazy_static::lazy_static;
+use serde::Serialize;
+use std::fmt::{self, Display, Formatter};
+use std::str::FromStr;
+
+lazy_static! {
+    /// `P1DT2H30M`, `PT0.5S`, years and months are parsed to be rejected
+    static ref grammar_pattern_iso8601: regex::Regex = regex::Regex::new(
+        r"(?i)^P(?:(\d+(?:[.,]\d+)?)Y)?(?:(\d+(?:[.,]\d+)?)M)?(?:(\d+(?:[.,]\d+)?)W)?(?:(\d+(?:[.,]\d+)?)D)?(?:T(?:(\d+(?:[.,]\d+)?)H)?(?:(\d+(?:[.,]\d+)?)M)?(?:(\d+(?:[.,]\d+)?)S)?)?$"
+    )
+    .unwrap();
+}
+
+/// A signed duration, from an ISO 8601 duration (`PT1H30M`), a humantime
+/// string (`1h30m`, `3d`) or a count of milliseconds.
+#[derive(Debug, Copy, Clone, Deref, PartialEq)]
+pub struct Duration(TimeDelta);
+
+impl From<TimeDelta> for Duration {
+    fn from(value: TimeDelta) -> Self {
+        Self(value)
+    }
+}
+
+impl FromStr for Duration {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let (negative, value) = match value.strip_prefix('-') {
+            Some(value) => (true, value.trim_start()),
+            None => (false, value.strip_prefix('+').unwrap_or(value).trim_start()),
+        };
+        let delta = if value.is_empty() {
+            return Err(anyhow!("Invalid duration: empty"));
+        } else if value.bytes().all(|it| it.is_ascii_digit()) {
+            TimeDelta::try_milliseconds(value.parse()?)
+                .ok_or_else(|| anyhow!("Duration out of range: {value}ms"))?
+        } else if value.starts_with(['P', 'p']) {
+            parse_iso8601(value)?
+        } else {
+            let duration = humantime::parse_duration(value)
+                .map_err(|err| anyhow!("Invalid duration: {s}, {err}"))?;
+            TimeDelta::from_std(duration).map_err(|_| anyhow!("Duration out of range: {s}"))?
+        };
+        Ok(Self(if negative { -delta } else { delta }))
+    }
+}
+
+fn parse_iso8601(value: &str) -> crate::Result<TimeDelta> {
+    let captures = grammar_pattern_iso8601
+        .captures(value)
+        .filter(|it| it.iter().skip(1).any(|it| it.is_some()))
+        .ok_or_else(|| anyhow!("Invalid ISO 8601 duration: {value}"))?;
+    if captures.get(1).is_some() || captures.get(2).is_some() {
+        return Err(anyhow!(
+            "Invalid duration: {value}, years and months have no fixed length, use days"
+        ));
+    }
+    let mut nanos = 0f64;
+    for (group, seconds) in [
+        (3, 604_800f64),
+        (4, 86_400f64),
+        (5, 3_600f64),
+        (6, 60f64),
+        (7, 1f64),
+    ] {
+        if let Some(number) = captures.get(group) {
+            let number: f64 = number.as_str().replace(',', ".").parse()?;
+            nanos += number * seconds * 1e9;
+        }
+    }
+    if nanos > i64::MAX as f64 {
+        return Err(anyhow!("Duration out of range: {value}"));
+    }
+    Ok(TimeDelta::nanoseconds(nanos.round() as i64))
+}
+
+/// How a duration is printed, all the forms if not set.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum DurationFormat {
+    Human,
+    Iso8601,
+    Clock,
+    Seconds,
+    Milliseconds,
+}
+
+impl FromStr for DurationFormat {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "human" | "h" => Ok(DurationFormat::Human),
+            "iso8601" | "iso" => Ok(DurationFormat::Iso8601),
+            "clock" | "c" => Ok(DurationFormat::Clock),
+            "s" | "seconds" => Ok(DurationFormat::Seconds),
+            "ms" | "milliseconds" => Ok(DurationFormat::Milliseconds),
+            _ => Err(anyhow!(
+                "Invalid duration format: {s}, expected human, iso8601, clock, s or ms"
+            )),
+        }
+    }
+}
+
+/// A duration in its human and machine forms.
+#[derive(Debug, Clone, Serialize)]
+pub struct DurationVal {
+    /// e.g. `1day 2h 30m`
+    human: String,
+    /// e.g. `PT26H30M`
+    iso8601: String,
+    /// e.g. `1d 02:30:00`
+    clock: String,
+    seconds: f64,
+    milliseconds: i64,
+}
+
+impl From<&Duration> for DurationVal {
+    fn from(Duration(delta): &Duration) -> Self {
+        let sign = if *delta < TimeDelta::zero() { "-" } else { "" };
+        let abs = delta.abs();
+        let human = match abs.to_std() {
+            Ok(it) if it.is_zero() => "0s".to_string(),
+            Ok(it) => humantime::format_duration(it).to_string(),
+            Err(_) => abs.to_string(),
+        };
+        let (hours, minutes, seconds) = (
+            abs.num_hours(),
+            abs.num_minutes() % 60,
+            abs.num_seconds() % 60,
+        );
+        let nanos = abs.subsec_nanos();
+        let fraction = if nanos == 0 {
+            String::new()
+        } else {
+            format!(".{nanos:09}").trim_end_matches('0').to_string()
+        };
+        let mut iso8601 = format!("{sign}PT");
+        if hours > 0 {
+            iso8601.push_str(&format!("{hours}H"));
+        }
+        if minutes > 0 {
+            iso8601.push_str(&format!("{minutes}M"));
+        }
+        if seconds > 0 || !fraction.is_empty() || abs.is_zero() {
+            iso8601.push_str(&format!("{seconds}{fraction}S"));
+        }
+        let days = match hours / 24 {
+            0 => String::new(),
+            days => format!("{days}d "),
+        };
+        let clock = format!(
+            "{sign}{days}{:02}:{minutes:02}:{seconds:02}{fraction}",
+            hours % 24
+        );
+        Self {
+            human: format!("{sign}{human}"),
+            iso8601,
+            clock,
+            seconds: delta.as_seconds_f64(),
+            milliseconds: delta.num_milliseconds(),
+        }
+    }
+}
+
+impl DurationVal {
+    /// One form, or all of them on aligned lines.
+    pub fn format(&self, format: Option<DurationFormat>) -> String {
+        match format {
+            Some(DurationFormat::Human) => self.human.clone(),
+            Some(DurationFormat::Iso8601) => self.iso8601.clone(),
+            Some(DurationFormat::Clock) => self.clock.clone(),
+            Some(DurationFormat::Seconds) => self.seconds.to_string(),
+            Some(DurationFormat::Milliseconds) => self.milliseconds.to_string(),
+            None => self.to_string(),
+        }
+    }
+}
+
+impl Display for DurationVal {
+    fn fmt(&self, f: &mut Formatter<'_>) -> fmt::Result {
+        writeln!(f, "human    {}", self.human)?;
+        writeln!(f, "iso8601  {}", self.iso8601)?;
+        writeln!(f, "clock    {}", self.clock)?;
+        writeln!(f, "seconds  {}", self.seconds)?;
+        write!(f, "ms       {}", self.milliseconds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Duration, DurationVal};
+    use chrono::TimeDelta;
+    use std::str::FromStr;
+
+    #[test]
+    fn parses_iso8601_humantime_and_milliseconds() {
+        let parse = |s: &str| Duration::from_str(s).map(|it| *it);
+        let expected = TimeDelta::minutes(90);
+        assert_eq!(parse("PT1H30M").unwrap(), expected);
+        assert_eq!(parse("pt1,5h").unwrap(), expected);
+        assert_eq!(parse("1h30m").unwrap(), expected);
+        assert_eq!(parse("1h 30min").unwrap(), expected);
+        assert_eq!(parse("5400000").unwrap(), expected);
+        assert_eq!(parse("-90m").unwrap(), -expected);
+        assert_eq!(parse("3d").unwrap(), TimeDelta::days(3));
+        assert_eq!(parse("P1W1D").unwrap(), TimeDelta::days(8));
+        assert_eq!(parse("PT0.25S").unwrap(), TimeDelta::milliseconds(250));
+        assert!(parse("P1M").is_err());
+        assert!(parse("PT").is_err());
+        assert!(parse("soon").is_err());
+    }
+
+    #[test]
+    fn formats_in_every_form() {
+        let val = DurationVal::from(&Duration::from_str("P1DT2H30M0.5S").unwrap());
+        assert_eq!(
+            val.to_string(),
+            "human    1day 2h 30m 500ms\niso8601  PT26H30M0.5S\nclock    1d 02:30:00.5\nseconds  95400.5\nms       95400500"
+        );
+        let val = DurationVal::from(&Duration::from_str("-45s").unwrap());
+        assert_eq!(val.iso8601, "-PT45S");
+        assert_eq!(val.clock, "-00:00:45");
+        assert_eq!(val.human, "-45s");
+        assert_eq!(
+            DurationVal::from(&Duration::from_str("0").unwrap()).iso8601,
+            "PT0S"
+        );
+    }
+}
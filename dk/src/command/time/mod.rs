@@ -1,8 +1,12 @@
 use crate::command::read_stdin;
 use anyhow::anyhow;
-use chrono::{FixedOffset, Utc};
+use chrono::{DateTime, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, Utc};
+use chrono_tz::Tz;
+pub use cron::{CronDialect, CronSchedule};
 use derive_more::{Deref, Display, From, FromStr};
+pub use duration::{Duration, DurationFormat, DurationVal};
 use serde::Serialize;
+use std::fmt::Display;
 use std::panic;
 use std::str::FromStr;
 
@@ -13,10 +17,10 @@ pub enum TimeCommand {
         #[arg(
             long,
             short,
-            help = "output timezone, alias tz, default to LOCAL",
+            help = "output timezone, an offset like +08:00 or an IANA name like Asia/Shanghai, alias tz, default to LOCAL",
             alias = "tz"
         )]
-        timezone: Option<FixedOffset>,
+        timezone: Option<Timezone>,
         #[arg(
             long,
             short,
@@ -25,10 +29,16 @@ pub enum TimeCommand {
         format: Option<TimeFormat>,
         #[arg(
             long,
-            help = "output unix-timestamp unit, s or ms, alias iu, default to ms",
+            help = "output unix-timestamp unit, s, ms, us or ns, alias iu, default to ms",
             alias = "iu"
         )]
         output_unit: Option<TimestampUnit>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "also show the time in these zones as a table, e.g. utc,local,Europe/Berlin,+09:00"
+        )]
+        zones: Vec<Timezone>,
     },
     #[clap(about = "time paser")]
     Parse {
@@ -39,17 +49,22 @@ pub enum TimeCommand {
         time: Time,
         #[arg(
             long,
-            help = "input unix-timestamp unit, s or ms, alias iu, default to ms",
+            help = "input unix-timestamp unit, s, ms, us, ns or auto to infer it from the magnitude, alias iu, default to ms",
             alias = "iu"
         )]
         input_unit: Option<TimestampUnit>,
         #[arg(
             long,
             short,
-            help = "input timezone, alias tz, default to local",
+            help = "timezone of input strings without an offset and of the output, an offset like +08:00 or an IANA name like Asia/Shanghai, alias tz, default to local",
             alias = "tz"
         )]
-        timezone: Option<FixedOffset>,
+        timezone: Option<Timezone>,
+        #[arg(
+            long,
+            help = "local time repeated when the clocks go back: earliest(default), latest or reject"
+        )]
+        ambiguous: Option<AmbiguousTime>,
         #[arg(
             long,
             short,
@@ -58,11 +73,150 @@ pub enum TimeCommand {
         format: Option<TimeFormat>,
         #[arg(
             long,
-            help = "output unix-timestamp unit, s or ms, alias ou, default to ms",
+            help = "output unix-timestamp unit, s, ms, us or ns, alias ou, default to ms",
             alias = "ou"
         )]
         output_unit: Option<TimestampUnit>,
+        #[arg(
+            long,
+            value_delimiter = ',',
+            help = "also show the time in these zones as a table, e.g. utc,local,Europe/Berlin,+09:00"
+        )]
+        zones: Vec<Timezone>,
     },
+    #[clap(about = "add a duration to a time, e.g. 1h30m, PT1H30M, -3d or milliseconds")]
+    Add(AddArgs),
+}
+
+/// The `time` subcommands, those of [TimeCommand] output a time, the others a duration or a schedule.
+#[derive(clap::Subcommand)]
+pub enum TimeSubcommand {
+    #[command(flatten)]
+    Time(TimeCommand),
+    #[clap(about = "the duration from one time to another")]
+    Diff(DiffArgs),
+    #[clap(about = "parse a duration and print it in human and machine forms")]
+    Duration(DurationArgs),
+    #[clap(about = "validate and explain a cron expression, and list its next runs")]
+    Cron(CronArgs),
+}
+
+/// How input times are read.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct TimeInputArgs {
+    #[arg(
+        long,
+        help = "input unix-timestamp unit, s, ms, us, ns or auto to infer it from the magnitude, alias iu, default to ms",
+        alias = "iu"
+    )]
+    pub input_unit: Option<TimestampUnit>,
+    #[arg(
+        long,
+        short,
+        help = "timezone of input strings without an offset and of output times, an offset like +08:00 or an IANA name like Asia/Shanghai, alias tz, default to local",
+        alias = "tz"
+    )]
+    pub timezone: Option<Timezone>,
+    #[arg(
+        long,
+        help = "local time repeated when the clocks go back: earliest(default), latest or reject"
+    )]
+    pub ambiguous: Option<AmbiguousTime>,
+}
+
+/// How output times are printed.
+#[derive(clap::Args, Debug, Clone, Default)]
+pub struct TimeOutputArgs {
+    #[arg(
+        long,
+        short,
+        help = "output time format: rfc3339(default), timestamp(ts) or custom format"
+    )]
+    pub format: Option<TimeFormat>,
+    #[arg(
+        long,
+        help = "output unix-timestamp unit, s, ms, us or ns, alias ou, default to ms",
+        alias = "ou"
+    )]
+    pub output_unit: Option<TimestampUnit>,
+}
+
+#[derive(clap::Args)]
+pub struct AddArgs {
+    #[arg(help = "input time, support unix-timestamp or string time, eg. 2023-01-01 12:00:00")]
+    pub time: Time,
+    #[arg(
+        help = "duration to add, iso8601 (PT1H30M), humantime (1h30m, 3d) or milliseconds, negative to subtract",
+        allow_hyphen_values = true
+    )]
+    pub duration: Duration,
+    #[command(flatten)]
+    pub input: TimeInputArgs,
+    #[command(flatten)]
+    pub output: TimeOutputArgs,
+}
+
+#[derive(clap::Args)]
+pub struct DiffArgs {
+    #[arg(help = "start time, support unix-timestamp or string time")]
+    pub from: Time,
+    #[arg(help = "end time, support unix-timestamp or string time")]
+    pub to: Time,
+    #[command(flatten)]
+    pub input: TimeInputArgs,
+    #[arg(
+        long,
+        short,
+        help = "output one form of the duration: human, iso8601, clock, s or ms, all of them if not set"
+    )]
+    pub format: Option<DurationFormat>,
+}
+
+#[derive(clap::Args)]
+pub struct DurationArgs {
+    #[arg(
+        help = "duration, iso8601 (PT1H30M), humantime (1h30m, 3d) or milliseconds",
+        allow_hyphen_values = true
+    )]
+    pub value: Duration,
+    #[arg(
+        long,
+        short,
+        help = "output one form of the duration: human, iso8601, clock, s or ms, all of them if not set"
+    )]
+    pub format: Option<DurationFormat>,
+}
+
+#[derive(clap::Args)]
+pub struct CronArgs {
+    #[arg(
+        help = "cron expression, unix (5 fields), spring (6 fields, second first) or quartz (7 fields, year last), or @daily-like nicknames"
+    )]
+    pub expression: String,
+    #[arg(
+        long,
+        short,
+        help = "cron dialect: unix, spring or quartz, detected from the number of fields if not set"
+    )]
+    pub dialect: Option<CronDialect>,
+    #[arg(
+        short = 'n',
+        long,
+        default_value_t = 5,
+        help = "number of next runs to list"
+    )]
+    pub count: usize,
+    #[arg(
+        long,
+        short,
+        help = "timezone the schedule runs in, an offset like +08:00 or an IANA name like Asia/Shanghai, alias tz, default to local",
+        alias = "tz"
+    )]
+    pub timezone: Option<Timezone>,
+    #[arg(long, help = "list the runs after this time instead of now")]
+    pub from: Option<Time>,
+    #[command(flatten)]
+    pub output: TimeOutputArgs,
 }
 
 #[derive(Debug, Clone, Display, Serialize)]
@@ -73,18 +227,69 @@ pub enum Time {
 #[derive(Debug, Clone, Display, Deref, From, FromStr, Serialize)]
 #[display("{_0}")]
 pub struct Timestring(String);
+mod cron;
+mod duration;
 mod timestring_guess;
 #[derive(Debug, Copy, Clone, Display, Deref, From, Serialize)]
 #[display("{_0}")]
 pub struct Timestamp(i64);
 
-#[derive(Debug, Copy, Clone, Display, Default)]
+#[derive(Debug, Copy, Clone, Display, Default, PartialEq, Serialize)]
 pub enum TimestampUnit {
     #[display("s")]
+    #[serde(rename = "s")]
     Seconds,
     #[default]
     #[display("ms")]
+    #[serde(rename = "ms")]
     Milliseconds,
+    #[display("us")]
+    #[serde(rename = "us")]
+    Microseconds,
+    #[display("ns")]
+    #[serde(rename = "ns")]
+    Nanoseconds,
+    /// Inferred from the magnitude of an input timestamp
+    #[display("auto")]
+    #[serde(rename = "auto")]
+    Auto,
+}
+
+impl TimestampUnit {
+    /// The unit of `timestamp`, by its magnitude for `Auto`: up to 11 digits are
+    /// seconds, 14 milliseconds, 17 microseconds, and nanoseconds beyond, which
+    /// holds for times between 1973 and 5138.
+    pub fn resolve(self, timestamp: i64) -> TimestampUnit {
+        if self != TimestampUnit::Auto {
+            return self;
+        }
+        match timestamp.unsigned_abs() {
+            0..100_000_000_000 => TimestampUnit::Seconds,
+            100_000_000_000..100_000_000_000_000 => TimestampUnit::Milliseconds,
+            100_000_000_000_000..100_000_000_000_000_000 => TimestampUnit::Microseconds,
+            _ => TimestampUnit::Nanoseconds,
+        }
+    }
+}
+
+/// A fixed offset, or an IANA zone of the embedded tz database following its DST rules.
+#[derive(Debug, Copy, Clone, Display, PartialEq)]
+pub enum Timezone {
+    #[display("local")]
+    Local,
+    #[display("{_0}")]
+    Offset(FixedOffset),
+    #[display("{_0}")]
+    Named(Tz),
+}
+
+/// Which of the two instants a local time means when the clocks go back.
+#[derive(Debug, Copy, Clone, Default, PartialEq)]
+pub enum AmbiguousTime {
+    #[default]
+    Earliest,
+    Latest,
+    Reject,
 }
 
 #[derive(Debug, Clone, Default)]
@@ -95,10 +300,30 @@ pub enum TimeFormat {
     Format(String),
 }
 
+impl super::Command for TimeSubcommand {
+    fn run(&self) -> crate::Result<()> {
+        match self {
+            TimeSubcommand::Time(command) => return command.run(),
+            TimeSubcommand::Diff(args) => println!("{}", args.run()?.format(args.format)),
+            TimeSubcommand::Duration(args) => println!("{}", args.run().format(args.format)),
+            TimeSubcommand::Cron(args) => println!("{}", args.run()?),
+        }
+        Ok(())
+    }
+}
+
 impl super::Command for TimeCommand {
     fn run(&self) -> crate::Result<()> {
-        let TimeFormatterVal { output: result, .. } = self.run_actual()?;
-        println!("{}", result);
+        let val = self.run_actual()?;
+        if let TimeCommand::Parse {
+            input_unit: Some(TimestampUnit::Auto),
+            ..
+        } = self
+            && let Some(unit) = val.input_unit
+        {
+            eprintln!("timestamp unit: {unit}");
+        }
+        println!("{val}");
         Ok(())
     }
 }
@@ -110,62 +335,291 @@ impl TimeCommand {
                 timezone,
                 format,
                 output_unit,
+                zones,
             } => {
-                let time =
-                    Utc::now().with_timezone(&timezone.unwrap_or(*chrono::Local::now().offset()));
-                let result = Self::time_formatter(&time, format, &output_unit.unwrap_or_default())?;
-                Ok(TimeFormatterVal {
-                    intput: Time::StringTime(Timestring(result.clone())),
-                    timestamp: Timestamp(time.timestamp_millis()),
-                    output: result,
-                })
+                // the input of `now` is its output
+                Self::formatter_val(
+                    None,
+                    &Utc::now(),
+                    timezone,
+                    zones,
+                    format,
+                    &output_unit.unwrap_or_default(),
+                )
             }
             TimeCommand::Parse {
                 time: input_time,
                 input_unit,
                 timezone,
+                ambiguous,
                 format,
                 output_unit,
+                zones,
             } => {
-                let time = match (input_time, input_unit.unwrap_or_default()) {
-                    (Time::Timestamp(time), TimestampUnit::Seconds) => {
-                        chrono::DateTime::from_timestamp(**time, 0)
-                            .ok_or(anyhow!("Invalid timestamp {}seconds", time))?
-                    }
-                    (Time::Timestamp(time), TimestampUnit::Milliseconds) => {
-                        chrono::DateTime::from_timestamp_millis(**time)
-                            .ok_or(anyhow!("Invalid timestamp {}milliseconds", time))?
+                let input = TimeInputArgs {
+                    input_unit: *input_unit,
+                    timezone: *timezone,
+                    ambiguous: *ambiguous,
+                };
+                let (time, input_unit) = input.parse_time(input_time)?;
+                let mut val = Self::formatter_val(
+                    Some(input_time.clone()),
+                    &time,
+                    timezone,
+                    zones,
+                    format,
+                    &output_unit.unwrap_or_default(),
+                )?;
+                val.input_unit = input_unit;
+                Ok(val)
+            }
+            TimeCommand::Add(args) => args.run(),
+        }
+    }
+}
+
+impl AddArgs {
+    pub fn run(&self) -> crate::Result<TimeFormatterVal> {
+        let (time, input_unit) = self.input.parse_time(&self.time)?;
+        let duration = *self.duration;
+        let time = time
+            .checked_add_signed(duration)
+            .ok_or_else(|| anyhow!("{time} plus {duration} is out of range"))?;
+        let mut val = TimeCommand::formatter_val(
+            Some(self.time.clone()),
+            &time,
+            &self.input.timezone,
+            &[],
+            &self.output.format,
+            &self.output.output_unit.unwrap_or_default(),
+        )?;
+        val.input_unit = input_unit;
+        Ok(val)
+    }
+}
+
+impl DiffArgs {
+    pub fn run(&self) -> crate::Result<DurationVal> {
+        let (from, _) = self.input.parse_time(&self.from)?;
+        let (to, _) = self.input.parse_time(&self.to)?;
+        Ok(DurationVal::from(&Duration::from(to - from)))
+    }
+}
+
+impl DurationArgs {
+    pub fn run(&self) -> DurationVal {
+        DurationVal::from(&self.value)
+    }
+}
+
+impl CronArgs {
+    /// The explanation and next runs of the expression.
+    pub fn run(&self) -> crate::Result<CronVal> {
+        let schedule = CronSchedule::parse(&self.expression, self.dialect)?;
+        let from = match &self.from {
+            Some(from) => {
+                let input = TimeInputArgs {
+                    timezone: self.timezone,
+                    ..Default::default()
+                };
+                input.parse_time(from)?.0
+            }
+            None => Utc::now(),
+        };
+        let zone = self.timezone.unwrap_or(Timezone::Local);
+        let runs = match zone {
+            Timezone::Local => schedule.next_runs(&from, &chrono::Local, self.count)?,
+            Timezone::Offset(offset) => schedule.next_runs(&from, &offset, self.count)?,
+            Timezone::Named(tz) => schedule.next_runs(&from, &tz, self.count)?,
+        };
+        let format = &self.output.format;
+        let unit = self.output.output_unit.unwrap_or_default();
+        let next = runs
+            .iter()
+            .map(|time| Ok(TimeCommand::zoned_formatter(time, &zone, format, &unit)?.time))
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(CronVal {
+            expression: self.expression.trim().to_string(),
+            dialect: schedule.dialect(),
+            fields: schedule.fields(),
+            description: schedule.description(),
+            notes: schedule.notes(),
+            zone: TimeCommand::zoned_formatter(&from, &zone, &None, &unit)?.describe(),
+            next,
+        })
+    }
+}
+
+impl TimeInputArgs {
+    /// The instant of an input timestamp or string, and the unit of a timestamp.
+    fn parse_time(
+        &self,
+        input_time: &Time,
+    ) -> crate::Result<(DateTime<Utc>, Option<TimestampUnit>)> {
+        let input_unit = match input_time {
+            Time::Timestamp(time) => Some(self.input_unit.unwrap_or_default().resolve(**time)),
+            Time::StringTime(_) => None,
+        };
+        let time = match (input_time, input_unit.unwrap_or_default()) {
+            (Time::Timestamp(time), TimestampUnit::Seconds) => {
+                chrono::DateTime::from_timestamp(**time, 0)
+                    .ok_or(anyhow!("Invalid timestamp {}seconds", time))?
+            }
+            (Time::Timestamp(time), TimestampUnit::Milliseconds) => {
+                chrono::DateTime::from_timestamp_millis(**time)
+                    .ok_or(anyhow!("Invalid timestamp {}milliseconds", time))?
+            }
+            (Time::Timestamp(time), TimestampUnit::Microseconds) => {
+                chrono::DateTime::from_timestamp_micros(**time)
+                    .ok_or(anyhow!("Invalid timestamp {}microseconds", time))?
+            }
+            (Time::Timestamp(time), TimestampUnit::Nanoseconds | TimestampUnit::Auto) => {
+                chrono::DateTime::from_timestamp_nanos(**time)
+            }
+            (Time::StringTime(time), _) => match timestring_guess::parse_naive(time) {
+                Some(naive) => {
+                    let ambiguous = self.ambiguous.unwrap_or_default();
+                    match self.timezone.unwrap_or(Timezone::Local) {
+                        Timezone::Local => from_local(&chrono::Local, &naive, ambiguous, "local")?,
+                        Timezone::Offset(offset) => from_local(&offset, &naive, ambiguous, offset)?,
+                        Timezone::Named(tz) => from_local(&tz, &naive, ambiguous, tz)?,
                     }
-                    (Time::StringTime(time), _) => chrono::DateTime::<Utc>::try_from(time)
-                        .map_err(|err| {
-                            log::debug!("Failed to parse time string: {}, error: {}", time, err);
-                            anyhow!("Invalid string time {time}")
-                        })?,
                 }
-                .with_timezone(&timezone.unwrap_or(*chrono::Local::now().offset()));
-                let result = Self::time_formatter(&time, format, &output_unit.unwrap_or_default())?;
-                Ok(TimeFormatterVal {
-                    intput: input_time.clone(),
-                    timestamp: Timestamp(time.timestamp_millis()),
-                    output: result,
+                None => chrono::DateTime::<Utc>::try_from(time).map_err(|err| {
+                    log::debug!("Failed to parse time string: {}, error: {}", time, err);
+                    anyhow!("Invalid string time {time}")
+                })?,
+            },
+        };
+        Ok((time, input_unit))
+    }
+}
+
+/// The instant of a local time in `zone`, failing for times skipped by a DST change.
+fn from_local<Z: chrono::TimeZone>(
+    zone: &Z,
+    naive: &NaiveDateTime,
+    ambiguous: AmbiguousTime,
+    name: impl Display,
+) -> crate::Result<DateTime<Utc>>
+where
+    Z::Offset: Display,
+{
+    match zone.from_local_datetime(naive) {
+        LocalResult::Single(time) => Ok(time.to_utc()),
+        LocalResult::Ambiguous(earliest, latest) => match ambiguous {
+            AmbiguousTime::Earliest => Ok(earliest.to_utc()),
+            AmbiguousTime::Latest => Ok(latest.to_utc()),
+            AmbiguousTime::Reject => Err(anyhow!(
+                "{naive} is ambiguous in {name}, it is both {} and {}",
+                earliest.to_rfc3339(),
+                latest.to_rfc3339()
+            )),
+        },
+        LocalResult::None => Err(anyhow!(
+            "{naive} does not exist in {name}, the clocks skip it at a DST change"
+        )),
+    }
+}
+
+impl TimeCommand {
+    /// The time in `timezone`, and in each of `zones` with their day relative to it.
+    fn formatter_val(
+        input: Option<Time>,
+        time: &DateTime<Utc>,
+        timezone: &Option<Timezone>,
+        zones: &[Timezone],
+        format: &Option<TimeFormat>,
+        unit: &TimestampUnit,
+    ) -> crate::Result<TimeFormatterVal> {
+        let main = Self::zoned_formatter(time, &timezone.unwrap_or(Timezone::Local), format, unit)?;
+        let zones = zones
+            .iter()
+            .map(|zone| {
+                let mut row = Self::zoned_formatter(time, zone, format, unit)?;
+                row.day = match (row.date - main.date).num_days() {
+                    0 => "same day".to_string(),
+                    days @ (1 | -1) => format!("{days:+} day"),
+                    days => format!("{days:+} days"),
+                };
+                Ok(row)
+            })
+            .collect::<crate::Result<Vec<_>>>()?;
+        Ok(TimeFormatterVal {
+            intput: input.unwrap_or_else(|| Time::StringTime(Timestring(main.time.clone()))),
+            timestamp: Timestamp(time.timestamp_millis()),
+            input_unit: None,
+            zone: main.describe(),
+            output: main.time,
+            zones,
+            timestamp_output: matches!(format, Some(TimeFormat::Timestamp)),
+        })
+    }
+
+    /// The time formatted in `timezone`, with the abbreviation of named zones.
+    fn zoned_formatter(
+        time: &DateTime<Utc>,
+        timezone: &Timezone,
+        format: &Option<TimeFormat>,
+        unit: &TimestampUnit,
+    ) -> crate::Result<ZonedTime> {
+        let zoned = |formatted: String, offset: FixedOffset, date: NaiveDate| ZonedTime {
+            zone: timezone.to_string(),
+            abbreviation: None,
+            offset: offset.to_string(),
+            time: formatted,
+            day: String::new(),
+            date,
+        };
+        match timezone {
+            Timezone::Local => {
+                let time = time.with_timezone(&chrono::Local);
+                let formatted = Self::time_formatter(&time, format, unit)?;
+                Ok(zoned(formatted, *time.offset(), time.date_naive()))
+            }
+            Timezone::Offset(offset) => {
+                let time = time.with_timezone(offset);
+                let formatted = Self::time_formatter(&time, format, unit)?;
+                Ok(zoned(formatted, *offset, time.date_naive()))
+            }
+            Timezone::Named(tz) => {
+                let time = time.with_timezone(tz);
+                let formatted = Self::time_formatter(&time, format, unit)?;
+                Ok(ZonedTime {
+                    abbreviation: Some(time.format("%Z").to_string()),
+                    ..zoned(formatted, time.offset().fix(), time.date_naive())
                 })
             }
         }
     }
-}
 
-impl TimeCommand {
-    fn time_formatter(
-        time: &chrono::DateTime<FixedOffset>,
+    fn time_formatter<Z: chrono::TimeZone>(
+        time: &DateTime<Z>,
         format: &Option<TimeFormat>,
         unit: &TimestampUnit,
-    ) -> crate::Result<String> {
+    ) -> crate::Result<String>
+    where
+        Z::Offset: Display + panic::RefUnwindSafe,
+    {
         let format = format.clone().unwrap_or_default();
         let result = match format {
             TimeFormat::RFC3339 => time.to_rfc3339(),
             TimeFormat::Timestamp => match unit {
                 TimestampUnit::Seconds => time.timestamp().to_string(),
                 TimestampUnit::Milliseconds => time.timestamp_millis().to_string(),
+                TimestampUnit::Microseconds => time.timestamp_micros().to_string(),
+                TimestampUnit::Nanoseconds => time
+                    .timestamp_nanos_opt()
+                    .ok_or(anyhow!(
+                        "{} is out of the range of nanosecond timestamps",
+                        time.to_rfc3339()
+                    ))?
+                    .to_string(),
+                TimestampUnit::Auto => {
+                    return Err(anyhow!(
+                        "auto is only an input unit, output one of s, ms, us or ns"
+                    ));
+                }
             },
             TimeFormat::Format(format) => panic::catch_unwind(|| time.format(&format).to_string())
                 .map_err(|_| anyhow!("Invalid time format"))?,
@@ -178,7 +632,127 @@ impl TimeCommand {
 pub struct TimeFormatterVal {
     intput: Time,
     timestamp: Timestamp,
+    /// The unit of an input timestamp, the inferred one for `auto`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    input_unit: Option<TimestampUnit>,
+    /// e.g. `America/New_York EDT -04:00`
+    zone: String,
     output: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    zones: Vec<ZonedTime>,
+    /// A timestamp has no zone, so none is printed under it
+    #[serde(skip)]
+    timestamp_output: bool,
+}
+
+impl std::fmt::Display for TimeFormatterVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.output)?;
+        if !self.timestamp_output {
+            write!(f, "\n{}", self.zone)?;
+        }
+        if !self.zones.is_empty() {
+            write!(f, "\n{}", self.zones_table())?;
+        }
+        Ok(())
+    }
+}
+
+/// A cron expression explained, with its next runs.
+#[derive(Debug, Clone, Serialize)]
+pub struct CronVal {
+    expression: String,
+    dialect: CronDialect,
+    /// `(name, value)` of each field, e.g. `("day-of-week", "MON-FRI")`
+    fields: Vec<(String, String)>,
+    description: String,
+    notes: Vec<String>,
+    /// The timezone of the runs, e.g. `Europe/Berlin CET +01:00`
+    zone: String,
+    next: Vec<String>,
+}
+
+impl std::fmt::Display for CronVal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{:<14}{}", "dialect", self.dialect)?;
+        for (name, value) in &self.fields {
+            writeln!(f, "{name:<14}{value}")?;
+        }
+        writeln!(f, "{:<14}{}", "meaning", self.description)?;
+        for note in &self.notes {
+            writeln!(f, "{:<14}{note}", "note")?;
+        }
+        write!(f, "{:<14}{}", "next runs", self.zone)?;
+        for time in &self.next {
+            write!(f, "\n{:<14}{time}", "")?;
+        }
+        Ok(())
+    }
+}
+
+/// One instant in one zone, a row of the `--zones` table.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZonedTime {
+    zone: String,
+    abbreviation: Option<String>,
+    offset: String,
+    time: String,
+    /// The calendar day relative to the output timezone, e.g. `+1 day`
+    day: String,
+    #[serde(skip)]
+    date: NaiveDate,
+}
+
+impl ZonedTime {
+    fn describe(&self) -> String {
+        match &self.abbreviation {
+            Some(abbreviation) if *abbreviation != self.zone => {
+                format!("{} {abbreviation} {}", self.zone, self.offset)
+            }
+            _ => format!("{} {}", self.zone, self.offset),
+        }
+    }
+}
+
+impl TimeFormatterVal {
+    /// The `--zones` rows aligned in columns under a header.
+    pub fn zones_table(&self) -> String {
+        let header = ["zone", "abbr", "offset", "time", "day"].map(str::to_string);
+        let rows: Vec<[String; 5]> = std::iter::once(header)
+            .chain(self.zones.iter().map(|it| {
+                [
+                    it.zone.clone(),
+                    it.abbreviation
+                        .clone()
+                        .filter(|abbreviation| *abbreviation != it.zone)
+                        .unwrap_or_default(),
+                    it.offset.clone(),
+                    it.time.clone(),
+                    it.day.clone(),
+                ]
+            }))
+            .collect();
+        let widths: Vec<usize> = (0..5)
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or_default()
+            })
+            .collect();
+        rows.iter()
+            .map(|row| {
+                row.iter()
+                    .zip(&widths)
+                    .map(|(cell, width)| format!("{cell:<width$}"))
+                    .collect::<Vec<_>>()
+                    .join("  ")
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
 }
 
 impl FromStr for Time {
@@ -208,11 +782,50 @@ impl FromStr for TimestampUnit {
         match s.as_str() {
             "s" | "seconds" => Ok(TimestampUnit::Seconds),
             "ms" | "milliseconds" => Ok(TimestampUnit::Milliseconds),
+            "us" | "µs" | "microseconds" => Ok(TimestampUnit::Microseconds),
+            "ns" | "nanoseconds" => Ok(TimestampUnit::Nanoseconds),
+            "auto" => Ok(TimestampUnit::Auto),
             _ => Err(anyhow!("Invalid time unit: {}", s)),
         }
     }
 }
 
+impl FromStr for Timezone {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.eq_ignore_ascii_case("local") {
+            return Ok(Timezone::Local);
+        }
+        if let Ok(offset) = FixedOffset::from_str(s) {
+            return Ok(Timezone::Offset(offset));
+        }
+        chrono_tz::TZ_VARIANTS
+            .iter()
+            .find(|it| it.name().eq_ignore_ascii_case(s))
+            .map(|it| Timezone::Named(*it))
+            .ok_or_else(|| {
+                anyhow!("Invalid timezone: {s}, expected an offset like +08:00 or an IANA name like Asia/Shanghai")
+            })
+    }
+}
+
+impl FromStr for AmbiguousTime {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "earliest" | "earlier" => Ok(AmbiguousTime::Earliest),
+            "latest" | "later" => Ok(AmbiguousTime::Latest),
+            "reject" => Ok(AmbiguousTime::Reject),
+            _ => Err(anyhow!(
+                "Invalid ambiguous time choice: {s}, expected earliest, latest or reject"
+            )),
+        }
+    }
+}
+
 impl FromStr for TimeFormat {
     type Err = anyhow::Error;
 
@@ -225,3 +838,200 @@ impl FromStr for TimeFormat {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        AddArgs, AmbiguousTime, DiffArgs, Time, TimeCommand, TimeFormat, TimeInputArgs,
+        TimeOutputArgs, TimestampUnit, Timezone,
+    };
+    use std::str::FromStr;
+
+    fn parse(
+        time: &str,
+        timezone: &str,
+        ambiguous: Option<AmbiguousTime>,
+    ) -> crate::Result<String> {
+        let command = TimeCommand::Parse {
+            time: Time::StringTime(time.to_string().into()),
+            input_unit: None,
+            timezone: Some(Timezone::from_str(timezone)?),
+            ambiguous,
+            format: None,
+            output_unit: None,
+            zones: vec![],
+        };
+        Ok(command.run_actual()?.output)
+    }
+
+    #[test]
+    fn parses_naive_times_in_named_zones_across_dst() {
+        assert_eq!(
+            parse("2026-07-01 12:00:00", "America/New_York", None).unwrap(),
+            "2026-07-01T12:00:00-04:00"
+        );
+        assert_eq!(
+            parse("2026-01-01T12:00:00", "america/new_york", None).unwrap(),
+            "2026-01-01T12:00:00-05:00"
+        );
+        assert_eq!(
+            parse("2026-07-01T12:00:00Z", "Asia/Shanghai", None).unwrap(),
+            "2026-07-01T20:00:00+08:00"
+        );
+        assert_eq!(
+            parse("2026-07-01 12:00", "+08:00", None).unwrap(),
+            "2026-07-01T12:00:00+08:00"
+        );
+        // clocks go back from 02:00 EDT to 01:00 EST
+        assert_eq!(
+            parse("2026-11-01 01:30:00", "America/New_York", None).unwrap(),
+            "2026-11-01T01:30:00-04:00"
+        );
+        assert_eq!(
+            parse(
+                "2026-11-01 01:30:00",
+                "America/New_York",
+                Some(AmbiguousTime::Latest)
+            )
+            .unwrap(),
+            "2026-11-01T01:30:00-05:00"
+        );
+        assert!(
+            parse(
+                "2026-11-01 01:30:00",
+                "America/New_York",
+                Some(AmbiguousTime::Reject)
+            )
+            .is_err()
+        );
+        // clocks go forward from 02:00 EST to 03:00 EDT
+        let err = parse("2026-03-08 02:30:00", "America/New_York", None).unwrap_err();
+        assert!(
+            err.to_string()
+                .contains("does not exist in America/New_York")
+        );
+        assert!(Timezone::from_str("Mars/Olympus").is_err());
+        assert!(TimeFormat::from_str("ts").is_ok());
+    }
+
+    #[test]
+    fn shows_an_instant_in_several_zones() {
+        let zones = ["utc", "Asia/Tokyo", "-10:00", "local"]
+            .map(|it| Timezone::from_str(it).unwrap())
+            .to_vec();
+        let command = TimeCommand::Parse {
+            time: Time::StringTime("2026-01-01T20:30:00Z".to_string().into()),
+            input_unit: None,
+            timezone: Some(Timezone::from_str("Europe/Berlin").unwrap()),
+            ambiguous: None,
+            format: Some(TimeFormat::from_str("%H:%M").unwrap()),
+            output_unit: None,
+            zones,
+        };
+        let val = command.run_actual().unwrap();
+        assert_eq!(val.output, "21:30");
+        let table = val.zones_table();
+        let lines: Vec<_> = table.lines().collect();
+        assert_eq!(lines[0], "zone        abbr  offset  time   day");
+        assert_eq!(lines[1], "UTC               +00:00  20:30  same day");
+        assert_eq!(lines[2], "Asia/Tokyo  JST   +09:00  05:30  +1 day");
+        assert_eq!(lines[3], "-10:00            -10:00  10:30  same day");
+        assert!(lines[4].starts_with("local"));
+        let printed = val.to_string();
+        let printed: Vec<_> = printed.lines().collect();
+        assert_eq!(
+            printed[..3],
+            ["21:30", "Europe/Berlin CET +01:00", lines[0]]
+        );
+        let json = serde_json::to_value(&val).unwrap();
+        assert_eq!(json["zone"], "Europe/Berlin CET +01:00");
+        assert_eq!(json["zones"][1]["abbreviation"], "JST");
+    }
+
+    #[test]
+    fn prints_the_zone_with_its_abbreviation_under_the_time() {
+        let parse = |format: Option<TimeFormat>| {
+            TimeCommand::Parse {
+                time: Time::StringTime("2026-07-01 12:00:00".to_string().into()),
+                input_unit: None,
+                timezone: Some(Timezone::from_str("America/New_York").unwrap()),
+                ambiguous: None,
+                format,
+                output_unit: None,
+                zones: vec![],
+            }
+            .run_actual()
+            .unwrap()
+            .to_string()
+        };
+        assert_eq!(
+            parse(None),
+            "2026-07-01T12:00:00-04:00\nAmerica/New_York EDT -04:00"
+        );
+        assert_eq!(parse(Some(TimeFormat::Timestamp)), "1782921600000");
+    }
+
+    #[test]
+    fn infers_timestamp_units_from_magnitude() {
+        let parse = |time: &str, unit: &str, output_unit: &str| {
+            TimeCommand::Parse {
+                time: Time::from_str(time).unwrap(),
+                input_unit: Some(TimestampUnit::from_str(unit).unwrap()),
+                timezone: Some(Timezone::from_str("utc").unwrap()),
+                ambiguous: None,
+                format: Some(TimeFormat::Timestamp),
+                output_unit: Some(TimestampUnit::from_str(output_unit).unwrap()),
+                zones: vec![],
+            }
+            .run_actual()
+        };
+        for (time, unit) in [
+            ("1700000000", TimestampUnit::Seconds),
+            ("1700000000123", TimestampUnit::Milliseconds),
+            ("1700000000123456", TimestampUnit::Microseconds),
+            ("1700000000123456789", TimestampUnit::Nanoseconds),
+        ] {
+            let val = parse(time, "auto", "s").unwrap();
+            assert_eq!(val.input_unit, Some(unit), "{time}");
+            assert_eq!(val.output, "1700000000");
+        }
+        assert_eq!(
+            parse("1700000000123456", "us", "ns").unwrap().output,
+            "1700000000123456000"
+        );
+        assert_eq!(parse("-1", "auto", "ms").unwrap().output, "-1000");
+        assert!(parse("1", "s", "auto").is_err());
+    }
+
+    #[test]
+    fn adds_and_diffs_durations() {
+        let utc = Some(Timezone::from_str("utc").unwrap());
+        let add = TimeCommand::Add(AddArgs {
+            time: Time::from_str("2026-03-08 01:30:00").unwrap(),
+            duration: "PT1H".parse().unwrap(),
+            input: TimeInputArgs {
+                timezone: Some(Timezone::from_str("America/New_York").unwrap()),
+                ..Default::default()
+            },
+            output: TimeOutputArgs::default(),
+        });
+        // an hour later the clocks have gone forward
+        assert_eq!(
+            add.run_actual().unwrap().output,
+            "2026-03-08T03:30:00-04:00"
+        );
+        let diff = DiffArgs {
+            from: Time::from_str("1700000000").unwrap(),
+            to: Time::from_str("2023-11-15T22:13:20Z").unwrap(),
+            input: TimeInputArgs {
+                input_unit: Some(TimestampUnit::Seconds),
+                timezone: utc,
+                ambiguous: None,
+            },
+            format: None,
+        };
+        let val = diff.run().unwrap();
+        assert_eq!(val.format(Some("iso".parse().unwrap())), "PT24H");
+        assert_eq!(val.format(Some("human".parse().unwrap())), "1day");
+    }
+}
@@ -1,6 +1,17 @@
 use super::Timestring;
+use chrono::{NaiveDate, NaiveDateTime};
 use std::convert::TryFrom;
 
+/// Formats of local times without an offset, interpreted in the given timezone.
+const NAIVE_FORMATS: [&str; 6] = [
+    "%Y-%m-%d %H:%M:%S%.f",
+    "%Y-%m-%dT%H:%M:%S%.f",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+    "%Y/%m/%d %H:%M:%S%.f",
+    "%Y/%m/%d %H:%M",
+];
+
 impl TryFrom<&Timestring> for chrono::DateTime<chrono::Utc> {
     type Error = anyhow::Error;
 
@@ -12,6 +23,20 @@ impl TryFrom<&Timestring> for chrono::DateTime<chrono::Utc> {
     }
 }
 
+/// A date and time without an offset, a date alone is its midnight.
+pub(super) fn parse_naive(Timestring(val): &Timestring) -> Option<NaiveDateTime> {
+    let val = val.trim();
+    NAIVE_FORMATS
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(val, format).ok())
+        .or_else(|| {
+            ["%Y-%m-%d", "%Y/%m/%d"]
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(val, format).ok())
+                .and_then(|it| it.and_hms_opt(0, 0, 0))
+        })
+}
+
 fn parse_from_rfc3339(val: &str) -> Option<chrono::DateTime<chrono::Utc>> {
     match chrono::DateTime::parse_from_rfc3339(val) {
         Ok(val) => Some(val.to_utc()),
@@ -1,4 +1,5 @@
-use dev_kit::command::formatter::FormattedValue;
+use dev_kit::command::formatter::{FormattedValue, FormattedValueType};
+use dev_kit::command::http::HttpClientOptions;
 use dev_kit::command::json::Json;
 use sha2::Digest;
 use std::str::FromStr;
@@ -16,17 +17,28 @@ impl JsonDiffState {
 }
 
 impl JsonDiffState {
-    pub async fn get_or_parse(&self, input: &str, reload: bool) -> Result<FormattedValue, String> {
+    pub async fn get_or_parse(
+        &self,
+        input: &str,
+        reload: bool,
+        input_type: Option<FormattedValueType>,
+    ) -> Result<FormattedValue, String> {
         let json_sha = {
-            let json_sha = &sha2::Sha256::digest(input.as_bytes())[..];
-            hex::encode(json_sha)
+            let mut hasher = sha2::Sha256::new();
+            hasher.update(input.as_bytes());
+            if let Some(input_type) = input_type {
+                hasher.update(format!("{input_type:?}"));
+            }
+            hex::encode(hasher.finalize())
         };
         if let (false, Some(parsed)) = (reload, self.cache.get(&json_sha).await) {
             return Ok(parsed);
         }
         let json = {
+            let options = HttpClientOptions::load_config().map_err(|e| e.to_string())?;
             let json = Json::from_str(input).map_err(|e| e.to_string())?;
-            FormattedValue::try_from(&json).map_err(|e| e.to_string())?
+            json.to_formatted_value(input_type, &options)
+                .map_err(|e| e.to_string())?
         };
         let _ = self.cache.insert(json_sha, json.clone()).await;
         Ok(json)
@@ -1,5 +1,6 @@
 use derive_more::{Deref, DerefMut, From};
-use dev_kit::command::formatter::FormattedValue;
+use dev_kit::command::formatter::{FormattedValue, FormattedValueType};
+use dev_kit::command::http::HttpClientOptions;
 use dev_kit::command::json::{Json, JsonpathMatch};
 use serde::{Deserialize, Serialize};
 use sha2::Digest;
@@ -164,6 +165,7 @@ impl JsonParserState {
         tab_id: &str,
         json_input_string: &str,
         reload: bool,
+        input_type: Option<FormattedValueType>,
     ) -> Result<&FormattedValue, String> {
         let config_dir = self.data_path.to_owned();
 
@@ -171,7 +173,14 @@ impl JsonParserState {
             let Some(tab) = self.tabs.get(tab_id) else {
                 return Err("Tab not found".to_string());
             };
-            let json_input_string_sha = hex::encode(sha2::Sha256::digest(json_input_string));
+            let json_input_string_sha = {
+                let mut hasher = sha2::Sha256::new();
+                hasher.update(json_input_string);
+                if let Some(input_type) = input_type {
+                    hasher.update(format!("{input_type:?}"));
+                }
+                hex::encode(hasher.finalize())
+            };
             let sha_eq =
                 json_input_string_sha.eq(tab.json_input_sha.as_deref().unwrap_or_default());
             (
@@ -191,7 +200,8 @@ impl JsonParserState {
                     .as_ref()
                     .expect("unexpected none json_output");
                 let json = Json::Filepath(path.to_path_buf());
-                let json_value = FormattedValue::try_from(&json)
+                let json_value = json
+                    .to_formatted_value(None, &HttpClientOptions::default())
                     .and_then(|it| it.convert(*ctype))
                     .map_err(|err| format!("{err}"))?;
                 let _ = tab.json_output_cache.replace(json_value);
@@ -211,8 +221,12 @@ impl JsonParserState {
                     let _ = tab.json_input.replace(InputSource { path });
                 }
                 let (formatted_value, pretty) = {
-                    let formatted_value =
-                        FormattedValue::from_str(json_input_string).map_err(|e| e.to_string())?;
+                    let formatted_value = HttpClientOptions::load_config()
+                        .and_then(|options| {
+                            Json::from_str(json_input_string)?
+                                .to_formatted_value(input_type, &options)
+                        })
+                        .map_err(|e| e.to_string())?;
                     let pretty = formatted_value
                         .to_string_pretty()
                         .map_err(|e| e.to_string())?;
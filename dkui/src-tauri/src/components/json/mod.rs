@@ -48,13 +48,15 @@ pub async fn jsonparser_query_json(
     json: String,
     query: Option<String>,
     query_type: Option<String>,
+    input_type: Option<String>,
     reload: bool,
     tab_id: String,
 ) -> Result<JsonparserQueryJson, String> {
+    let input_type = parse_input_type(input_type)?;
     let mut app_state = state.write().await;
     let value = app_state
         .jsonparser
-        .get_or_parse(&tab_id, &json, reload)
+        .get_or_parse(&tab_id, &json, reload, input_type)
         .await?;
     let arr = Json::query_beauty(
         value,
@@ -95,10 +97,15 @@ pub async fn jsondiff_query_json(
     input: String,
     query: Option<String>,
     query_type: Option<String>,
+    input_type: Option<String>,
     reload: bool,
 ) -> Result<JsondiffQueryJson, String> {
+    let input_type = parse_input_type(input_type)?;
     let app_state = state.write().await;
-    let value = app_state.jsondiff.get_or_parse(&input, reload).await?;
+    let value = app_state
+        .jsondiff
+        .get_or_parse(&input, reload, input_type)
+        .await?;
     let arr = Json::query_beauty(
         &value,
         query.as_deref(),
@@ -124,9 +131,14 @@ pub async fn jsondiff_search_json_paths(
     json: String,
     query: Option<String>,
     query_type: Option<String>,
+    input_type: Option<String>,
 ) -> Result<Vec<JsonpathMatch>, String> {
+    let input_type = parse_input_type(input_type)?;
     let app_state = state.read().await;
-    let formatted_value = app_state.jsondiff.get_or_parse(&json, false).await?;
+    let formatted_value = app_state
+        .jsondiff
+        .get_or_parse(&json, false, input_type)
+        .await?;
     let value = formatted_value.try_into().map_err(|err| format!("{err}"))?;
     let query_type = query_type.and_then(|s| QueryType::from_str(&s).ok());
     match Json::search_paths(&value, query.as_deref(), query_type) {
@@ -143,10 +155,20 @@ pub async fn jsondiff_diff_json(
     query: Option<String>,
     query_type: Option<String>,
     diff_tool: Option<String>,
+    left_input_type: Option<String>,
+    right_input_type: Option<String>,
 ) -> Result<(), String> {
+    let left_input_type = parse_input_type(left_input_type)?;
+    let right_input_type = parse_input_type(right_input_type)?;
     let app_state = state.read().await;
-    let left_val = app_state.jsondiff.get_or_parse(&left, false).await?;
-    let right_val = app_state.jsondiff.get_or_parse(&right, false).await?;
+    let left_val = app_state
+        .jsondiff
+        .get_or_parse(&left, false, left_input_type)
+        .await?;
+    let right_val = app_state
+        .jsondiff
+        .get_or_parse(&right, false, right_input_type)
+        .await?;
     let query_type = query_type.and_then(|s| QueryType::from_str(&s).ok());
     let tool = if let Some(t) = diff_tool {
         DiffTool::from_str(&t).map_err(|e| e.to_string())?
@@ -164,6 +186,15 @@ pub async fn jsondiff_diff_json(
     Ok(())
 }
 
+/// An empty input type means the type is guessed.
+fn parse_input_type(input_type: Option<String>) -> Result<Option<FormattedValueType>, String> {
+    input_type
+        .filter(|it| !it.is_empty())
+        .map(|it| FormattedValueType::from_str(&it))
+        .transpose()
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn get_available_diff_tools() -> Vec<String> {
     use strum::IntoEnumIterator;
@@ -1,5 +1,4 @@
-use chrono::FixedOffset;
-use dev_kit::command::time::{Time, TimeCommand, TimeFormat, TimestampUnit};
+use dev_kit::command::time::{Time, TimeCommand, TimeFormat, TimestampUnit, Timezone};
 use std::str::FromStr;
 
 #[tauri::command]
@@ -7,17 +6,25 @@ pub fn parse_time(
     time: String,
     timezone: Option<String>,
     format: Option<String>,
+    zones: Option<Vec<String>>,
 ) -> Result<String, String> {
     let cmd = TimeCommand::Parse {
         time: Time::from_str(&time).map_err(|e| e.to_string())?,
         input_unit: Some(TimestampUnit::Milliseconds),
         timezone: timezone
             .as_deref()
-            .and_then(|tz| FixedOffset::from_str(tz).ok()),
+            .and_then(|tz| Timezone::from_str(tz).ok()),
+        ambiguous: None,
         format: format
             .as_deref()
             .and_then(|fmt| TimeFormat::from_str(fmt).ok()),
         output_unit: Some(TimestampUnit::Milliseconds),
+        zones: zones
+            .unwrap_or_default()
+            .iter()
+            .map(|zone| Timezone::from_str(zone))
+            .collect::<Result<_, _>>()
+            .map_err(|e| e.to_string())?,
     };
     let result = cmd.run_actual().map_err(|e| e.to_string())?;
     serde_json::to_string(&result).map_err(|err| err.to_string())
@@ -9,8 +9,10 @@ fn time_and_qrcode_work_offline() {
         time: Time::from_str("0").unwrap(),
         input_unit: Some(TimestampUnit::Seconds),
         timezone: None,
+        ambiguous: None,
         format: Some(TimeFormat::Timestamp),
         output_unit: Some(TimestampUnit::Seconds),
+        zones: vec![],
     };
     assert!(command.run_actual().is_ok());
 